@@ -0,0 +1,118 @@
+//! Compare two frame logs and report performance regressions.
+//!
+//! Usage: `frame_compare [OPTIONS] <BASELINE> <CANDIDATE>`
+//!
+//! Both logs are CSV files written by `game_time::framelog::FrameLog`. The exit status is
+//! `0` if no regression was found, `1` if a regression threshold was exceeded and `2` if
+//! the arguments or logs could not be read.
+extern crate game_time;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::process;
+use std::str::FromStr;
+
+use game_time::GameTime;
+use game_time::compare::{self, CompareConfig};
+use game_time::framelog::FrameLogReader;
+use game_time::framerate::{HitchBaseline, LinearAverageSampler};
+use game_time::framerate::sample::DEFAULT_NUM_SAMPLES;
+
+const USAGE: &str = "\
+Usage: frame_compare [OPTIONS] <BASELINE> <CANDIDATE>
+
+Options:
+    --mean-threshold <PCT>   Mean frame time increase considered a regression (default 5)
+    --p99-threshold <PCT>    99th percentile frame time increase considered a regression
+    --hitch-threshold <PCT>  Increase in percentage of hitching frames considered a regression
+    --alpha <P>              Significance level for the mean frame time (default 0.05)
+    --target-fps <FPS>       Measure hitches against this frame rate instead of the median
+    --hitch-multiplier <X>   Multiple of the expected frame time that is a hitch (default 2)
+    --samples <N>            Number of frames averaged for the frame rate (default 64)
+    -h, --help               Print this message";
+
+fn main() {
+    match run() {
+        Ok(true) => process::exit(1),
+        Ok(false) => {}
+        Err(msg) => {
+            eprintln!("error: {}\n\n{}", msg, USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+/// Run the comparison, returning whether a regression was found.
+fn run() -> Result<bool, String> {
+    let mut config = CompareConfig::new();
+    let mut samples = DEFAULT_NUM_SAMPLES;
+    let mut paths = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| -> Result<f64, String> {
+            parse_value(name, args.next())
+        };
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "--mean-threshold" => {
+                config.mean_threshold(Some(value(&arg)? / 100.0));
+            }
+            "--p99-threshold" => {
+                config.p99_threshold(Some(value(&arg)? / 100.0));
+            }
+            "--hitch-threshold" => {
+                config.hitch_rate_threshold(Some(value(&arg)? / 100.0));
+            }
+            "--alpha" => {
+                config.significance_level(value(&arg)?);
+            }
+            "--target-fps" => {
+                config
+                    .target_frame_rate(value(&arg)?)
+                    .hitch_baseline(HitchBaseline::TargetFrameTime);
+            }
+            "--hitch-multiplier" => {
+                config.hitch_multiplier(value(&arg)?);
+            }
+            "--samples" => {
+                samples = parse_value(&arg, args.next())?;
+                if samples == 0 {
+                    return Err("--samples must be at least 1".to_string());
+                }
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ => paths.push(arg),
+        }
+    }
+    if paths.len() != 2 {
+        return Err("expected a baseline and a candidate log".to_string());
+    }
+
+    let baseline = read_log(&paths[0]).map_err(|err| format!("{}: {}", paths[0], err))?;
+    let candidate = read_log(&paths[1]).map_err(|err| format!("{}: {}", paths[1], err))?;
+    let sampler = LinearAverageSampler::with_max_samples(samples);
+    let result = compare::compare(&baseline, &candidate, &sampler, &config)
+        .map_err(|err| err.to_string())?;
+    println!("{}", result);
+    Ok(result.is_regression())
+}
+
+/// Parse the value of the option `name`.
+fn parse_value<T: FromStr>(name: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing value for {}", name))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", name, value))
+}
+
+fn read_log(path: &str) -> io::Result<Vec<GameTime>> {
+    let file = File::open(path)?;
+    FrameLogReader::new(BufReader::new(file))
+        .map(|record| record.map(|r| r.into_time()))
+        .collect()
+}
@@ -0,0 +1,494 @@
+//! An in-game calendar derived from game time.
+//!
+//! [`GameCalendar`](./struct.GameCalendar.html) maps the total game time of a simulation to
+//! in-game days, hours, months and seasons, such as "Day 14, 06:30, Spring". The length of
+//! an in-game day is given in game time, so the calendar follows the clock multiplier and
+//! stops while the game is paused.
+//!
+//! Boundaries crossed between two frames are reported by
+//! [`crossings`](./struct.GameCalendar.html#method.crossings), which can be used to fire
+//! events on a new day or at a given time of day.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use game_time::{GameClock, FloatDuration};
+//! use game_time::calendar::{GameCalendar, Season};
+//! use game_time::step;
+//!
+//! // One in-game minute passes every game second.
+//! let mut calendar = GameCalendar::new(FloatDuration::minutes(24.0));
+//! let epoch = calendar.hour_length() * 6.0;
+//! calendar.set_epoch(epoch);
+//!
+//! let mut clock = GameClock::new();
+//! let step = step::ConstantStep::new(FloatDuration::minutes(1.0));
+//! let mut previous = clock.last_frame_time().clone();
+//!
+//! for _ in 0..24 {
+//!     let time = clock.tick(&step);
+//!     if calendar.crossings(&previous, &time).days() > 0 {
+//!         println!("A new day begins: {}", calendar.time_of(&time));
+//!     }
+//!     previous = time;
+//! }
+//!
+//! let now = calendar.time_of(&previous);
+//! assert_eq!(now.day(), 2);
+//! assert_eq!(now.hour(), 6);
+//! assert_eq!(now.season(), Season::Spring);
+//! assert_eq!(now.to_string(), "Day 2, 06:00, Spring");
+//! ```
+use std::fmt;
+
+use chrono::TimeZone;
+use float_duration::FloatDuration;
+
+use clock::GameTime;
+
+/// The default number of hours in an in-game day.
+pub const DEFAULT_HOURS_PER_DAY: u32 = 24;
+/// The default number of days in an in-game month.
+pub const DEFAULT_DAYS_PER_MONTH: u32 = 30;
+/// The default number of days in an in-game season.
+pub const DEFAULT_DAYS_PER_SEASON: u32 = 90;
+
+const MINUTES_PER_HOUR: u32 = 60;
+const SECONDS_PER_MINUTE: f64 = 60.0;
+
+/// A season of the in-game year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Season {
+    /// The first season of the year.
+    Spring,
+    /// The second season of the year.
+    Summer,
+    /// The third season of the year.
+    Autumn,
+    /// The last season of the year.
+    Winter,
+}
+
+/// A configurable mapping from game time to in-game calendar time.
+///
+/// Days are numbered from 1 and start at midnight. Months and seasons are counted
+/// independently from the first day, with seasons cycling from `Spring` to `Winter`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameCalendar {
+    day_length: FloatDuration,
+    hours_per_day: u32,
+    days_per_month: u32,
+    days_per_season: u32,
+    epoch: FloatDuration,
+}
+
+/// A point in time on an in-game calendar.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalendarTime {
+    day: u64,
+    hour: u32,
+    minute: u32,
+    second: f64,
+    month: u64,
+    day_of_month: u32,
+    season: Season,
+    day_of_season: u32,
+}
+
+/// The number of calendar boundaries crossed between two points in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CalendarCrossings {
+    hours: u64,
+    days: u64,
+    months: u64,
+    seasons: u64,
+}
+
+impl Season {
+    /// Return the season following this one.
+    pub fn next(self) -> Season {
+        match self {
+            Season::Spring => Season::Summer,
+            Season::Summer => Season::Autumn,
+            Season::Autumn => Season::Winter,
+            Season::Winter => Season::Spring,
+        }
+    }
+
+    fn from_index(index: u64) -> Season {
+        match index % 4 {
+            0 => Season::Spring,
+            1 => Season::Summer,
+            2 => Season::Autumn,
+            _ => Season::Winter,
+        }
+    }
+}
+
+impl fmt::Display for Season {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Season::Spring => "Spring",
+            Season::Summer => "Summer",
+            Season::Autumn => "Autumn",
+            Season::Winter => "Winter",
+        };
+        f.write_str(name)
+    }
+}
+
+impl GameCalendar {
+    /// Construct a new `GameCalendar` where each in-game day lasts `day_length` of game time.
+    pub fn new(day_length: FloatDuration) -> GameCalendar {
+        GameCalendar {
+            day_length,
+            hours_per_day: DEFAULT_HOURS_PER_DAY,
+            days_per_month: DEFAULT_DAYS_PER_MONTH,
+            days_per_season: DEFAULT_DAYS_PER_SEASON,
+            epoch: FloatDuration::zero(),
+        }
+    }
+
+    /// Return the game time taken by each in-game day.
+    pub fn day_length(&self) -> FloatDuration {
+        self.day_length
+    }
+    /// Set the game time taken by each in-game day.
+    pub fn set_day_length(&mut self, day_length: FloatDuration) -> &mut GameCalendar {
+        self.day_length = day_length;
+        self
+    }
+    /// Return the game time taken by each in-game hour.
+    pub fn hour_length(&self) -> FloatDuration {
+        self.day_length / self.hours_per_day as f64
+    }
+    /// Return the number of hours in each day.
+    pub fn hours_per_day(&self) -> u32 {
+        self.hours_per_day
+    }
+    /// Set the number of hours in each day.
+    ///
+    /// Panics if `hours` is zero.
+    pub fn set_hours_per_day(&mut self, hours: u32) -> &mut GameCalendar {
+        assert!(hours > 0, "hours_per_day must be nonzero");
+        self.hours_per_day = hours;
+        self
+    }
+    /// Return the number of days in each month.
+    pub fn days_per_month(&self) -> u32 {
+        self.days_per_month
+    }
+    /// Set the number of days in each month.
+    ///
+    /// Panics if `days` is zero.
+    pub fn set_days_per_month(&mut self, days: u32) -> &mut GameCalendar {
+        assert!(days > 0, "days_per_month must be nonzero");
+        self.days_per_month = days;
+        self
+    }
+    /// Return the number of days in each season.
+    pub fn days_per_season(&self) -> u32 {
+        self.days_per_season
+    }
+    /// Set the number of days in each season.
+    ///
+    /// Panics if `days` is zero.
+    pub fn set_days_per_season(&mut self, days: u32) -> &mut GameCalendar {
+        assert!(days > 0, "days_per_season must be nonzero");
+        self.days_per_season = days;
+        self
+    }
+    /// Return the calendar time elapsed at zero game time, measured in game time.
+    pub fn epoch(&self) -> FloatDuration {
+        self.epoch
+    }
+    /// Set the calendar time elapsed at zero game time, measured in game time.
+    ///
+    /// For example, an epoch of six hours starts the game on day 1 at 06:00.
+    pub fn set_epoch(&mut self, epoch: FloatDuration) -> &mut GameCalendar {
+        self.epoch = epoch;
+        self
+    }
+
+    /// Return the calendar time at the total game time `game_time`.
+    ///
+    /// Game times before the start of day 1 are treated as its start.
+    pub fn time_at(&self, game_time: FloatDuration) -> CalendarTime {
+        let day_length = self.day_length.as_seconds();
+        let hour_length = day_length / self.hours_per_day as f64;
+        let minute_length = hour_length / MINUTES_PER_HOUR as f64;
+
+        let mut remaining = self.calendar_seconds(game_time);
+        let day_index = (remaining / day_length).floor() as u64;
+        remaining -= day_index as f64 * day_length;
+        let hour = ((remaining / hour_length).floor() as u32).min(self.hours_per_day - 1);
+        remaining -= hour as f64 * hour_length;
+        let minute = ((remaining / minute_length).floor() as u32).min(MINUTES_PER_HOUR - 1);
+        remaining -= minute as f64 * minute_length;
+
+        let days_per_month = self.days_per_month as u64;
+        let days_per_season = self.days_per_season as u64;
+        CalendarTime {
+            day: day_index + 1,
+            hour,
+            minute,
+            second: remaining / minute_length * SECONDS_PER_MINUTE,
+            month: day_index / days_per_month + 1,
+            day_of_month: (day_index % days_per_month) as u32 + 1,
+            season: Season::from_index(day_index / days_per_season),
+            day_of_season: (day_index % days_per_season) as u32 + 1,
+        }
+    }
+    /// Return the calendar time of the frame `time`.
+    pub fn time_of<Tz: TimeZone>(&self, time: &GameTime<Tz>) -> CalendarTime {
+        self.time_at(time.total_game_time())
+    }
+    /// Return the total game time at the given day, hour, minute and second.
+    ///
+    /// `day` is numbered from 1. The result is negative if the time is before the epoch.
+    pub fn game_time_at(&self, day: u64, hour: u32, minute: u32, second: f64) -> FloatDuration {
+        let hour_length = self.hour_length();
+        let minute_length = hour_length / MINUTES_PER_HOUR as f64;
+        let calendar_time = self.day_length * day.saturating_sub(1) as f64 +
+            hour_length * hour as f64 + minute_length * minute as f64 +
+            minute_length * (second / SECONDS_PER_MINUTE);
+        calendar_time - self.epoch
+    }
+    /// Return the total game time at the calendar time `time`.
+    pub fn to_game_time(&self, time: &CalendarTime) -> FloatDuration {
+        self.game_time_at(time.day, time.hour, time.minute, time.second)
+    }
+
+    /// Return the calendar boundaries crossed between the frames `previous` and `current`.
+    ///
+    /// A boundary is crossed if it lies after `previous` and at or before `current`.
+    pub fn crossings<Tz: TimeZone>(
+        &self,
+        previous: &GameTime<Tz>,
+        current: &GameTime<Tz>,
+    ) -> CalendarCrossings {
+        let start = self.calendar_seconds(previous.total_game_time());
+        let end = self.calendar_seconds(current.total_game_time());
+        let day_length = self.day_length.as_seconds();
+        CalendarCrossings {
+            hours: boundaries(start, end, 0.0, self.hour_length().as_seconds()),
+            days: boundaries(start, end, 0.0, day_length),
+            months: boundaries(start, end, 0.0, day_length * self.days_per_month as f64),
+            seasons: boundaries(start, end, 0.0, day_length * self.days_per_season as f64),
+        }
+    }
+    /// Return true if the time of day `hour:minute` was passed between the frames `previous`
+    /// and `current`.
+    ///
+    /// This can be used to fire events such as sunrise at a fixed time each day.
+    pub fn crossed_time_of_day<Tz: TimeZone>(
+        &self,
+        previous: &GameTime<Tz>,
+        current: &GameTime<Tz>,
+        hour: u32,
+        minute: u32,
+    ) -> bool {
+        let start = self.calendar_seconds(previous.total_game_time());
+        let end = self.calendar_seconds(current.total_game_time());
+        let offset = (self.game_time_at(1, hour, minute, 0.0) + self.epoch).as_seconds();
+        boundaries(start, end, offset, self.day_length.as_seconds()) > 0
+    }
+
+    fn calendar_seconds(&self, game_time: FloatDuration) -> f64 {
+        (game_time + self.epoch).as_seconds().max(0.0)
+    }
+}
+
+impl CalendarTime {
+    /// The day number, starting from 1.
+    pub fn day(&self) -> u64 {
+        self.day
+    }
+    /// The hour of the day, starting from 0.
+    pub fn hour(&self) -> u32 {
+        self.hour
+    }
+    /// The minute of the hour, from 0 to 59.
+    pub fn minute(&self) -> u32 {
+        self.minute
+    }
+    /// The in-game seconds into the minute, from 0 up to 60.
+    pub fn second(&self) -> f64 {
+        self.second
+    }
+    /// The month number, starting from 1.
+    pub fn month(&self) -> u64 {
+        self.month
+    }
+    /// The day of the month, starting from 1.
+    pub fn day_of_month(&self) -> u32 {
+        self.day_of_month
+    }
+    /// The current season.
+    pub fn season(&self) -> Season {
+        self.season
+    }
+    /// The day of the season, starting from 1.
+    pub fn day_of_season(&self) -> u32 {
+        self.day_of_season
+    }
+}
+
+impl fmt::Display for CalendarTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Day {}, {:02}:{:02}, {}",
+            self.day,
+            self.hour,
+            self.minute,
+            self.season
+        )
+    }
+}
+
+impl CalendarCrossings {
+    /// The number of hour boundaries crossed.
+    pub fn hours(&self) -> u64 {
+        self.hours
+    }
+    /// The number of day boundaries crossed.
+    pub fn days(&self) -> u64 {
+        self.days
+    }
+    /// The number of month boundaries crossed.
+    pub fn months(&self) -> u64 {
+        self.months
+    }
+    /// The number of season boundaries crossed.
+    pub fn seasons(&self) -> u64 {
+        self.seasons
+    }
+}
+
+/// Count the boundaries at `offset + n * period` in the range `(start, end]`.
+fn boundaries(start: f64, end: f64, offset: f64, period: f64) -> u64 {
+    if end <= start {
+        return 0;
+    }
+    let first = ((start - offset) / period).floor();
+    let last = ((end - offset) / period).floor();
+    (last - first) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::GameClock;
+    use step;
+
+    fn calendar() -> GameCalendar {
+        let mut calendar = GameCalendar::new(FloatDuration::minutes(24.0));
+        calendar.set_days_per_month(10).set_days_per_season(20);
+        calendar
+    }
+
+    #[test]
+    fn test_time_at() {
+        let calendar = calendar();
+        assert_eq!(calendar.hour_length(), FloatDuration::minutes(1.0));
+
+        let start = calendar.time_at(FloatDuration::zero());
+        assert_eq!(start.day(), 1);
+        assert_eq!(start.hour(), 0);
+        assert_eq!(start.month(), 1);
+        assert_eq!(start.season(), Season::Spring);
+
+        let time = calendar.time_at(FloatDuration::minutes(24.0 * 33.0 + 6.5) +
+            FloatDuration::seconds(0.25));
+        assert_eq!(time.day(), 34);
+        assert_eq!(time.hour(), 6);
+        assert_eq!(time.minute(), 30);
+        assert!(relative_eq!(time.second(), 15.0, epsilon = 1e-6));
+        assert_eq!(time.month(), 4);
+        assert_eq!(time.day_of_month(), 4);
+        assert_eq!(time.season(), Season::Summer);
+        assert_eq!(time.day_of_season(), 14);
+        assert_eq!(time.to_string(), "Day 34, 06:30, Summer");
+
+        let winter = calendar.time_at(FloatDuration::minutes(24.0 * 60.0));
+        assert_eq!(winter.season(), Season::Winter);
+        assert_eq!(winter.season().next(), Season::Spring);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut calendar = calendar();
+        calendar.set_hours_per_day(20);
+        let epoch = calendar.hour_length() * 6.0;
+        calendar.set_epoch(epoch);
+
+        assert_eq!(calendar.time_at(FloatDuration::zero()).hour(), 6);
+        assert_eq!(calendar.game_time_at(1, 6, 0, 0.0), FloatDuration::zero());
+
+        let game_time = calendar.game_time_at(14, 18, 45, 30.0);
+        let time = calendar.time_at(game_time);
+        assert_eq!(time.day(), 14);
+        assert_eq!(time.hour(), 18);
+        assert_eq!(time.minute(), 45);
+        assert!(relative_eq!(time.second(), 30.0, epsilon = 1e-6));
+        assert!(relative_eq!(
+            calendar.to_game_time(&time).as_seconds(),
+            game_time.as_seconds(),
+            epsilon = 1e-9
+        ));
+    }
+
+    #[test]
+    fn test_crossings() {
+        let calendar = calendar();
+        let mut clock = GameClock::new();
+        let step = step::ConstantStep::new(FloatDuration::minutes(10.0));
+        let mut previous = clock.last_frame_time().clone();
+
+        let mut days = 0;
+        let mut months = 0;
+        let mut seasons = 0;
+        let mut sunrises = 0;
+        for _ in 0..(24 * 44 / 10) {
+            let time = clock.tick(&step);
+            let crossings = calendar.crossings(&previous, &time);
+            assert_eq!(crossings.hours(), 10);
+            days += crossings.days();
+            months += crossings.months();
+            seasons += crossings.seasons();
+            if calendar.crossed_time_of_day(&previous, &time, 6, 0) {
+                sunrises += 1;
+            }
+            previous = time;
+        }
+
+        assert_eq!(calendar.time_of(&previous).day(), 44);
+        assert_eq!(days, 43);
+        assert_eq!(months, 4);
+        assert_eq!(seasons, 2);
+        assert_eq!(sunrises, 44);
+        assert_eq!(
+            calendar.crossings(&previous, &previous),
+            CalendarCrossings::default()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "hours_per_day must be nonzero")]
+    fn test_zero_hours_per_day() {
+        GameCalendar::new(FloatDuration::minutes(20.0)).set_hours_per_day(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "days_per_month must be nonzero")]
+    fn test_zero_days_per_month() {
+        GameCalendar::new(FloatDuration::minutes(20.0)).set_days_per_month(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "days_per_season must be nonzero")]
+    fn test_zero_days_per_season() {
+        GameCalendar::new(FloatDuration::minutes(20.0)).set_days_per_season(0);
+    }
+}
@@ -4,14 +4,19 @@
 //! provides two primary types: `GameClock`, a "clock" for tracking frames
 //! and time progression within the simulation and `GameTime`, a specific
 //! point in time within the simulation.
+//!
+//! Both types are generic over the `chrono::TimeZone` used to record wall
+//! times, defaulting to `chrono::Local`. A clock using `chrono::Utc` avoids
+//! converting to local time on every frame.
 use std::thread;
 use std::time;
 
-use chrono;
+use chrono::{self, TimeZone};
 use float_duration::{FloatDuration, TimePoint};
-use step::TimeStep;
+use step::StatefulStep;
 
 use framerate::FrameCount;
+use history::FrameHistory;
 
 /// A specific point of time in a simulation.
 ///
@@ -19,14 +24,40 @@ use framerate::FrameCount;
 /// fixed time. A `GameTime` object is usually created by calling
 /// [`tick`](./struct.GameClock.html#method.tick) on a [`GameClock`](./struct.GameClock.html)
 /// object.
+///
+/// The wall time at the start of the frame is recorded in the time zone `Tz`
+/// of the clock that created it.
 #[derive(Debug, Clone)]
-pub struct GameTime {
-    frame_start_time: chrono::DateTime<chrono::Local>,
+pub struct GameTime<Tz: TimeZone = chrono::Local> {
+    frame_start_time: chrono::DateTime<Tz>,
     total_wall_time: FloatDuration,
     total_game_time: FloatDuration,
     elapsed_game_time: FloatDuration,
     elapsed_wall_time: FloatDuration,
     frame_number: u64,
+    clock_multiplier: f64,
+}
+
+/// The timing of a frame, independent of the time zone of its wall time.
+///
+/// This is implemented by [`GameTime`](./struct.GameTime.html) in every time zone. Frame
+/// rate counters and samplers take a `&dyn FrameTiming`, so they accept frames from any
+/// clock and can themselves be used as trait objects.
+pub trait FrameTiming {
+    /// The game time at the start of the frame.
+    fn total_game_time(&self) -> FloatDuration;
+    /// The wall time elapsed since the start of the simulation.
+    fn total_wall_time(&self) -> FloatDuration;
+    /// The amount of game time that passed since the previous frame.
+    fn elapsed_game_time(&self) -> FloatDuration;
+    /// The amount of wall time that passed since the previous frame.
+    fn elapsed_wall_time(&self) -> FloatDuration;
+    /// The amount of elapsed wall time since the start of the frame.
+    fn elapsed_time_since_frame_start(&self) -> FloatDuration;
+    /// The index of the frame.
+    fn frame_number(&self) -> u64;
+    /// The clock multiplier in effect when the frame was started.
+    fn clock_multiplier(&self) -> f64;
 }
 
 /// Time-tracking for use in real-time simulations.
@@ -48,12 +79,13 @@ pub struct GameTime {
 /// the `GameTime` object for that frame. This object can then be passed
 /// to the rest of the simulation independently of `GameClock`.
 #[derive(Debug, Clone)]
-pub struct GameClock {
-    last_frame_time: GameTime,
-    start_wall_time: chrono::DateTime<chrono::Local>,
+pub struct GameClock<Tz: TimeZone = chrono::Local> {
+    last_frame_time: GameTime<Tz>,
+    start_wall_time: chrono::DateTime<Tz>,
     total_game_time: time::Duration,
     current_frame: u64,
     clock_multiplier: f64,
+    history: Option<FrameHistory<String, Tz>>,
 }
 
 /// A [`GameClock`](./struct.GameClock.html) builder,
@@ -63,9 +95,9 @@ pub struct GameClock {
 /// most cases, using [`GameClock::new()`](./struct.GameClock.html#method.new) is good enough.
 /// However, it can be useful to have more control in some situations, especially testing.
 #[derive(Debug, Clone)]
-pub struct GameClockBuilder {
+pub struct GameClockBuilder<Tz: TimeZone = chrono::Local> {
     start_game_time: time::Duration,
-    start_wall_time: chrono::DateTime<chrono::Local>,
+    start_wall_time: chrono::DateTime<Tz>,
     start_frame: u64,
     clock_multiplier: f64,
 }
@@ -74,23 +106,16 @@ impl GameClock {
     /// Construct a new `GameClock` object, initialized to start at
     /// zero game time and a wall time of `chrono::Local::now()`.
     pub fn new() -> GameClock {
-        let now = chrono::Local::now();
-        let start_game_time = GameTime {
-            frame_start_time: now,
-            total_wall_time: FloatDuration::zero(),
-            total_game_time: FloatDuration::zero(),
-            elapsed_game_time: FloatDuration::zero(),
-            elapsed_wall_time: FloatDuration::zero(),
-            frame_number: 0,
-        };
+        GameClock::with_time_zone(chrono::Local)
+    }
+}
 
-        GameClock {
-            last_frame_time: start_game_time,
-            start_wall_time: now,
-            total_game_time: time::Duration::new(0, 0),
-            current_frame: 0,
-            clock_multiplier: 1.0,
-        }
+impl<Tz: TimeZone> GameClock<Tz> {
+    /// Construct a new `GameClock` object recording wall times in the time zone `tz`.
+    ///
+    /// The clock is initialized to start at zero game time and the current wall time.
+    pub fn with_time_zone(tz: Tz) -> GameClock<Tz> {
+        GameClockBuilder::with_time_zone(tz).build()
     }
 
     /// Return the current frame number.
@@ -101,11 +126,11 @@ impl GameClock {
         self.current_frame
     }
     /// Return the wall time when the `GameClock` was created.
-    pub fn start_wall_time(&self) -> chrono::DateTime<chrono::Local> {
-        self.start_wall_time
+    pub fn start_wall_time(&self) -> chrono::DateTime<Tz> {
+        self.start_wall_time.clone()
     }
     /// Return the wall time at the start of the current frame.
-    pub fn frame_start_time(&self) -> chrono::DateTime<chrono::Local> {
+    pub fn frame_start_time(&self) -> chrono::DateTime<Tz> {
         self.last_frame_time().frame_start_time()
     }
     /// Return the total elapsed wall time at the start of the current frame.
@@ -118,12 +143,10 @@ impl GameClock {
 
     /// Return the amount of wall time elapsed since the start of the current frame.
     pub fn frame_elapsed_time(&self) -> FloatDuration {
-        chrono::Local::now()
-            .float_duration_since(self.last_frame_time().frame_start_time())
-            .unwrap()
+        self.last_frame_time().elapsed_time_since_frame_start()
     }
     /// Return the [`GameTime`](./struct.GameTime.html) for the current frame.
-    pub fn last_frame_time(&self) -> &GameTime {
+    pub fn last_frame_time(&self) -> &GameTime<Tz> {
         &self.last_frame_time
     }
     /// Return the rate at which game time is increasing.
@@ -131,29 +154,59 @@ impl GameClock {
         self.clock_multiplier
     }
     /// Set the rate at which game time is increasing.
-    pub fn set_clock_multiplier(&mut self, val: f64) -> &mut GameClock {
+    pub fn set_clock_multiplier(&mut self, val: f64) -> &mut GameClock<Tz> {
         self.clock_multiplier = val;
         self
     }
 
+    /// Start keeping a history of the last `max_frames` frames.
+    ///
+    /// Each frame is recorded in the [`FrameHistory`](../history/struct.FrameHistory.html)
+    /// when `tick` is called. If a history is already kept, its size is changed instead.
+    pub fn enable_history(&mut self, max_frames: usize) -> &mut GameClock<Tz> {
+        match self.history {
+            Some(ref mut history) => {
+                history.set_max_frames(max_frames);
+            }
+            None => self.history = Some(FrameHistory::new(max_frames)),
+        }
+        self
+    }
+    /// Stop keeping a history of frames, returning the history kept so far.
+    pub fn disable_history(&mut self) -> Option<FrameHistory<String, Tz>> {
+        self.history.take()
+    }
+    /// Return the history of recent frames, if one is kept.
+    pub fn history(&self) -> Option<&FrameHistory<String, Tz>> {
+        self.history.as_ref()
+    }
+    /// Return the history of recent frames mutably, if one is kept.
+    ///
+    /// This can be used to annotate recorded frames.
+    pub fn history_mut(&mut self) -> Option<&mut FrameHistory<String, Tz>> {
+        self.history.as_mut()
+    }
+
     /// Mark the start of a new frame, updating time statistics.
     ///
     /// The `GameTime` for the new frame is returned. This gives the time
     /// statistics for the entirety of the current frame. It is cached and
     /// can be later obtained by calling `last_frame_time`.
     ///
-    /// `time_step` is a [`TimeStep`](../step/trait.TimeStep.html) reference used to
-    /// compute the elapsed game time for the frame..
+    /// `time_step` is a [`StatefulStep`](../step/trait.StatefulStep.html) used to compute
+    /// the elapsed game time for the frame. This is usually a reference to a
+    /// [`TimeStep`](../step/trait.TimeStep.html), or a mutable reference to a step that keeps
+    /// state between frames.
     ///
-    /// The wall time for the start of the frame is computed via `chrono::Local::now()` at
-    /// the start of the function. In order to override this to use a different clock
-    /// or for debugging purposes, see
+    /// The wall time for the start of the frame is the current time in the clock's
+    /// time zone, taken at the start of the function. In order to override this to use
+    /// a different clock or for debugging purposes, see
     /// [`tick_with_wall_time`](./struct.GameClock.html#methods.tick_with_wall_time).
-    pub fn tick<T>(&mut self, time_step: &T) -> GameTime
+    pub fn tick<S>(&mut self, time_step: S) -> GameTime<Tz>
     where
-        T: TimeStep + ?Sized,
+        S: StatefulStep,
     {
-        let frame_start = chrono::Local::now();
+        let frame_start = now_in(&self.start_wall_time.timezone());
         self.tick_with_wall_time(time_step, frame_start)
     }
 
@@ -161,36 +214,49 @@ impl GameClock {
     ///
     /// This function is like `tick` but allows for the start time for the
     /// frame to be specified.
-    pub fn tick_with_wall_time<T>(
+    pub fn tick_with_wall_time<S>(
         &mut self,
-        time_step: &T,
-        frame_start: chrono::DateTime<chrono::Local>,
-    ) -> GameTime
+        mut time_step: S,
+        frame_start: chrono::DateTime<Tz>,
+    ) -> GameTime<Tz>
     where
-        T: TimeStep + ?Sized,
+        S: StatefulStep,
     {
         self.current_frame += 1;
 
         let elapsed_wall_time = frame_start
+            .clone()
             .float_duration_since(self.frame_start_time())
             .unwrap();
 
-        let elapsed_game_time = time_step.time_step(&elapsed_wall_time) * self.clock_multiplier;
+        let step = time_step.next_step(
+            &elapsed_wall_time,
+            &self.last_frame_time,
+            self.clock_multiplier,
+        );
+        let elapsed_game_time = step * self.clock_multiplier;
         let total_game_time = self.total_game_time + elapsed_game_time.to_std().unwrap();
 
         self.total_game_time = total_game_time;
 
+        let total_wall_time = frame_start
+            .clone()
+            .float_duration_since(self.start_wall_time())
+            .unwrap();
+
         let time = GameTime {
             frame_start_time: frame_start,
-            total_wall_time: FloatDuration::from(
-                frame_start.signed_duration_since(self.start_wall_time),
-            ),
+            total_wall_time,
             total_game_time: FloatDuration::from(total_game_time),
             elapsed_game_time,
             elapsed_wall_time,
             frame_number: self.current_frame,
+            clock_multiplier: self.clock_multiplier,
         };
 
+        if let Some(ref mut history) = self.history {
+            history.record(&time);
+        }
         self.last_frame_time = time.clone();
 
         time
@@ -243,7 +309,28 @@ impl Default for GameClock {
     }
 }
 
-impl GameTime {
+impl<Tz: TimeZone> GameTime<Tz> {
+    /// Construct a `GameTime` from its individual values.
+    pub(crate) fn from_parts(
+        frame_start_time: chrono::DateTime<Tz>,
+        total_wall_time: FloatDuration,
+        total_game_time: FloatDuration,
+        elapsed_game_time: FloatDuration,
+        elapsed_wall_time: FloatDuration,
+        frame_number: u64,
+        clock_multiplier: f64,
+    ) -> GameTime<Tz> {
+        GameTime {
+            frame_start_time,
+            total_wall_time,
+            total_game_time,
+            elapsed_game_time,
+            elapsed_wall_time,
+            frame_number,
+            clock_multiplier,
+        }
+    }
+
     /// The game time at the time of creation of this `GameTime` object.
     pub fn total_game_time(&self) -> FloatDuration {
         self.total_game_time
@@ -253,8 +340,8 @@ impl GameTime {
         self.total_wall_time
     }
     /// The wall time at the time of creation of this `GameTime` object.
-    pub fn frame_start_time(&self) -> chrono::DateTime<chrono::Local> {
-        self.frame_start_time
+    pub fn frame_start_time(&self) -> chrono::DateTime<Tz> {
+        self.frame_start_time.clone()
     }
     /// The amount of game time that passed since the previous frame.
     pub fn elapsed_game_time(&self) -> FloatDuration {
@@ -269,9 +356,9 @@ impl GameTime {
     /// This value is computed from the current instant when called, based
     /// on the frame start time. This can be used for intra-frame profiling.
     pub fn elapsed_time_since_frame_start(&self) -> FloatDuration {
-        chrono::Local::now()
-            .float_duration_since(self.frame_start_time())
-            .unwrap()
+        FloatDuration::from_chrono(
+            chrono::Utc::now().signed_duration_since(self.frame_start_time.clone()),
+        )
     }
     /// The index of the current frame.
     ///
@@ -280,6 +367,10 @@ impl GameTime {
     pub fn frame_number(&self) -> u64 {
         self.frame_number
     }
+    /// The clock multiplier in effect when the frame was started.
+    pub fn clock_multiplier(&self) -> f64 {
+        self.clock_multiplier
+    }
     /// Return the instantaneous frame rate between the last and current frames.
     ///
     /// The "instantaneous" frame rate is computed from the last frame's elapsed
@@ -290,6 +381,42 @@ impl GameTime {
     pub fn instantaneous_frame_rate(&self) -> f64 {
         1.0 / self.elapsed_game_time.as_seconds()
     }
+    /// Return the same `GameTime` with its wall time expressed in the time zone `tz`.
+    pub fn with_timezone<Tz2: TimeZone>(&self, tz: &Tz2) -> GameTime<Tz2> {
+        GameTime {
+            frame_start_time: self.frame_start_time.with_timezone(tz),
+            total_wall_time: self.total_wall_time,
+            total_game_time: self.total_game_time,
+            elapsed_game_time: self.elapsed_game_time,
+            elapsed_wall_time: self.elapsed_wall_time,
+            frame_number: self.frame_number,
+            clock_multiplier: self.clock_multiplier,
+        }
+    }
+}
+
+impl<Tz: TimeZone> FrameTiming for GameTime<Tz> {
+    fn total_game_time(&self) -> FloatDuration {
+        self.total_game_time
+    }
+    fn total_wall_time(&self) -> FloatDuration {
+        self.total_wall_time
+    }
+    fn elapsed_game_time(&self) -> FloatDuration {
+        self.elapsed_game_time
+    }
+    fn elapsed_wall_time(&self) -> FloatDuration {
+        self.elapsed_wall_time
+    }
+    fn elapsed_time_since_frame_start(&self) -> FloatDuration {
+        GameTime::elapsed_time_since_frame_start(self)
+    }
+    fn frame_number(&self) -> u64 {
+        self.frame_number
+    }
+    fn clock_multiplier(&self) -> f64 {
+        self.clock_multiplier
+    }
 }
 
 impl GameClockBuilder {
@@ -298,9 +425,18 @@ impl GameClockBuilder {
     /// Calling `build` on the returned object returns immediately gives the same
     /// result as `GameClock::new()`.
     pub fn new() -> GameClockBuilder {
+        GameClockBuilder::with_time_zone(chrono::Local)
+    }
+}
+
+impl<Tz: TimeZone> GameClockBuilder<Tz> {
+    /// Construct a new `GameClockBuilder` for a clock recording wall times in the time zone `tz`.
+    ///
+    /// The initial wall time defaults to the current time in `tz`.
+    pub fn with_time_zone(tz: Tz) -> GameClockBuilder<Tz> {
         GameClockBuilder {
             start_game_time: time::Duration::new(0, 0),
-            start_wall_time: chrono::Local::now(),
+            start_wall_time: now_in(&tz),
             start_frame: 0,
             clock_multiplier: 1.0,
         }
@@ -309,51 +445,50 @@ impl GameClockBuilder {
     /// Set the initial game time when the game is started.
     ///
     /// Defaults to zero.
-    pub fn start_game_time(&mut self, time: time::Duration) -> &mut GameClockBuilder {
+    pub fn start_game_time(&mut self, time: time::Duration) -> &mut GameClockBuilder<Tz> {
         self.start_game_time = time;
         self
     }
     /// Set the initial wall time when the game is started.
     ///
-    /// Defaults to `chrono::Local::now()`.
-    pub fn start_wall_time(
-        &mut self,
-        time: chrono::DateTime<chrono::Local>,
-    ) -> &mut GameClockBuilder {
+    /// Defaults to the current time.
+    pub fn start_wall_time(&mut self, time: chrono::DateTime<Tz>) -> &mut GameClockBuilder<Tz> {
         self.start_wall_time = time;
         self
     }
     /// Set the initial frame number.
     ///
     /// Defaults to `0`.
-    pub fn start_frame(&mut self, frame_num: u64) -> &mut GameClockBuilder {
+    pub fn start_frame(&mut self, frame_num: u64) -> &mut GameClockBuilder<Tz> {
         self.start_frame = frame_num;
         self
     }
     /// Set the initial clock multiplier.
     ///
     /// Defaults to `1.0`.
-    pub fn clock_multiplier(&mut self, multiplier: f64) -> &mut GameClockBuilder {
+    pub fn clock_multiplier(&mut self, multiplier: f64) -> &mut GameClockBuilder<Tz> {
         self.clock_multiplier = multiplier;
         self
     }
     /// Construct a `GameClock` object with the set parameters.
-    pub fn build(&self) -> GameClock {
+    pub fn build(&self) -> GameClock<Tz> {
         let start_game_time = GameTime {
-            frame_start_time: self.start_wall_time,
+            frame_start_time: self.start_wall_time.clone(),
             total_wall_time: FloatDuration::zero(),
             total_game_time: FloatDuration::from(self.start_game_time),
             elapsed_game_time: FloatDuration::zero(),
             elapsed_wall_time: FloatDuration::zero(),
             frame_number: self.start_frame,
+            clock_multiplier: self.clock_multiplier,
         };
 
         GameClock {
             last_frame_time: start_game_time,
-            start_wall_time: self.start_wall_time,
+            start_wall_time: self.start_wall_time.clone(),
             total_game_time: self.start_game_time,
             current_frame: self.start_frame,
             clock_multiplier: self.clock_multiplier,
+            history: None,
         }
     }
 }
@@ -364,6 +499,11 @@ impl Default for GameClockBuilder {
     }
 }
 
+/// Return the current wall time in the time zone `tz`.
+pub(crate) fn now_in<Tz: TimeZone>(tz: &Tz) -> chrono::DateTime<Tz> {
+    chrono::Utc::now().with_timezone(tz)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -467,6 +607,7 @@ mod tests {
         let mut clock = GameClock::new();
         clock.set_clock_multiplier(2.0);
         assert_eq!(clock.clock_multiplier(), 2.0);
+        assert_eq!(clock.last_frame_time().clock_multiplier(), 1.0);
 
         let step = step::VariableStep::new();
         let start_time = clock.start_wall_time();
@@ -489,6 +630,33 @@ mod tests {
                 FloatDuration::from_chrono(wall_dt)
             );
             assert_eq!(frame_time.instantaneous_frame_rate(), 0.5);
+            assert_eq!(frame_time.clock_multiplier(), 2.0);
         }
     }
+
+    #[test]
+    fn test_utc_clock() {
+        let start_time = chrono::Utc.with_ymd_and_hms(2017, 6, 1, 12, 0, 0).unwrap();
+        let mut clock = GameClockBuilder::with_time_zone(chrono::Utc)
+            .start_wall_time(start_time)
+            .build();
+        assert_eq!(clock.start_wall_time(), start_time);
+
+        let step = step::VariableStep::new();
+        let frame_start = start_time + chrono::Duration::milliseconds(20);
+        let time = clock.tick_with_wall_time(&step, frame_start);
+        assert_eq!(time.frame_start_time(), frame_start);
+        assert_eq!(time.elapsed_wall_time(), FloatDuration::milliseconds(20.0));
+        assert_eq!(time.total_wall_time(), FloatDuration::milliseconds(20.0));
+
+        let local = time.with_timezone(&Local);
+        assert_eq!(local.frame_start_time(), frame_start);
+        assert_eq!(local.frame_number(), time.frame_number());
+        assert_eq!(local.total_game_time(), time.total_game_time());
+
+        let mut clock = GameClock::with_time_zone(chrono::Utc);
+        let time = clock.tick(&step);
+        assert!(time.frame_start_time() <= chrono::Utc::now());
+        assert!(time.elapsed_time_since_frame_start() >= FloatDuration::zero());
+    }
 }
@@ -0,0 +1,733 @@
+//! Comparison of recorded frame timings for performance regression detection.
+//!
+//! [`compare`](./fn.compare.html) computes [`FrameStats`](./struct.FrameStats.html) for a
+//! baseline and a candidate recording, such as those read from a
+//! [`FrameLogReader`](../framelog/struct.FrameLogReader.html), and tests whether the
+//! candidate is significantly slower using Welch's t-test on the frame times. Each recording
+//! must contain at least [`MIN_FRAMES`](./constant.MIN_FRAMES.html) frames.
+//!
+//! Average frame rates and hitch counts are computed by running the frames through a
+//! [`FrameRateSampler`](../framerate/sample/trait.FrameRateSampler.html) and a
+//! [`HitchDetector`](../framerate/hitch/struct.HitchDetector.html), so the numbers match
+//! those reported in-game by the same types.
+//!
+//! The `frame_compare` binary provides a command line interface for comparing two frame logs.
+use std::error::Error;
+use std::fmt;
+
+use chrono::TimeZone;
+use float_duration::FloatDuration;
+
+use clock::GameTime;
+use framerate::{FrameCount, FrameCounter, FrameRateSampler, HitchBaseline, HitchDetector};
+use framerate::sample;
+
+/// The default fractional increase in mean frame time considered a regression.
+pub const DEFAULT_MEAN_THRESHOLD: f64 = 0.05;
+/// The default significance level for the mean frame time test.
+pub const DEFAULT_SIGNIFICANCE_LEVEL: f64 = 0.05;
+/// The percentiles of frame time reported in `FrameStats`.
+pub const PERCENTILES: [f64; 4] = [0.5, 0.9, 0.95, 0.99];
+/// The fewest frames a recording must contain to be compared.
+pub const MIN_FRAMES: usize = 2;
+
+/// Settings for comparing frame recordings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompareConfig {
+    target_frame_rate: f64,
+    hitch_baseline: HitchBaseline,
+    hitch_multiplier: f64,
+    significance_level: f64,
+    mean_threshold: Option<f64>,
+    p99_threshold: Option<f64>,
+    hitch_rate_threshold: Option<f64>,
+}
+
+/// Frame time statistics of a single recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameStats {
+    num_frames: usize,
+    mean_frame_time: FloatDuration,
+    std_dev_frame_time: FloatDuration,
+    percentiles: Vec<FloatDuration>,
+    max_frame_time: FloatDuration,
+    mean_frame_rate: f64,
+    hitch_count: u64,
+}
+
+/// The result of comparing a candidate recording against a baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    baseline: FrameStats,
+    candidate: FrameStats,
+    t_statistic: f64,
+    p_value: f64,
+    significant: bool,
+    regressions: Vec<String>,
+}
+
+/// The reason two recordings could not be compared.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareError {
+    /// A recording has fewer than `MIN_FRAMES` frames.
+    InsufficientData {
+        /// The number of frames in the baseline recording.
+        baseline_frames: usize,
+        /// The number of frames in the candidate recording.
+        candidate_frames: usize,
+    },
+    /// A frame has an elapsed wall time that is negative or not finite.
+    InvalidFrameTime {
+        /// `"baseline"` or `"candidate"`.
+        recording: &'static str,
+        /// The frame number of the invalid frame.
+        frame_number: u64,
+        /// The elapsed wall time of the frame, in seconds.
+        seconds: f64,
+    },
+}
+
+impl CompareConfig {
+    /// Construct a new `CompareConfig` with default settings.
+    ///
+    /// By default, a regression is a statistically significant increase in mean frame time
+    /// of more than `DEFAULT_MEAN_THRESHOLD`.
+    pub fn new() -> CompareConfig {
+        CompareConfig {
+            target_frame_rate: 60.0,
+            hitch_baseline: HitchBaseline::RollingMedian(sample::DEFAULT_NUM_SAMPLES),
+            hitch_multiplier: ::framerate::hitch::DEFAULT_HITCH_MULTIPLIER,
+            significance_level: DEFAULT_SIGNIFICANCE_LEVEL,
+            mean_threshold: Some(DEFAULT_MEAN_THRESHOLD),
+            p99_threshold: None,
+            hitch_rate_threshold: None,
+        }
+    }
+
+    /// Set the target frame rate used for hitch detection against the target frame time.
+    ///
+    /// Defaults to `60.0`.
+    pub fn target_frame_rate(&mut self, val: f64) -> &mut CompareConfig {
+        self.target_frame_rate = val;
+        self
+    }
+    /// Set the baseline hitches are measured against.
+    ///
+    /// Defaults to a rolling median of `DEFAULT_NUM_SAMPLES` frames.
+    pub fn hitch_baseline(&mut self, val: HitchBaseline) -> &mut CompareConfig {
+        self.hitch_baseline = val;
+        self
+    }
+    /// Set the multiple of the expected frame time before a frame is a hitch.
+    pub fn hitch_multiplier(&mut self, val: f64) -> &mut CompareConfig {
+        self.hitch_multiplier = val;
+        self
+    }
+    /// Set the p-value below which a difference in mean frame time is significant.
+    pub fn significance_level(&mut self, val: f64) -> &mut CompareConfig {
+        self.significance_level = val;
+        self
+    }
+    /// Set the fractional increase in mean frame time considered a regression.
+    ///
+    /// The increase must also be statistically significant. `None` disables the check.
+    pub fn mean_threshold(&mut self, val: Option<f64>) -> &mut CompareConfig {
+        self.mean_threshold = val;
+        self
+    }
+    /// Set the fractional increase in 99th percentile frame time considered a regression.
+    ///
+    /// `None` disables the check, which is the default.
+    pub fn p99_threshold(&mut self, val: Option<f64>) -> &mut CompareConfig {
+        self.p99_threshold = val;
+        self
+    }
+    /// Set the increase in the fraction of frames that hitch considered a regression.
+    ///
+    /// For example, `0.01` allows one more hitch per hundred frames. `None` disables the
+    /// check, which is the default.
+    pub fn hitch_rate_threshold(&mut self, val: Option<f64>) -> &mut CompareConfig {
+        self.hitch_rate_threshold = val;
+        self
+    }
+}
+
+impl Default for CompareConfig {
+    fn default() -> CompareConfig {
+        CompareConfig::new()
+    }
+}
+
+impl FrameStats {
+    /// Compute the statistics of a recording.
+    ///
+    /// The mean frame rate is the average of the values reported by `sampler` over all
+    /// frames. Frame times are the elapsed wall time of each frame.
+    pub fn compute<S, Tz>(
+        times: &[GameTime<Tz>],
+        sampler: S,
+        config: &CompareConfig,
+    ) -> FrameStats
+    where
+        S: FrameRateSampler,
+        Tz: TimeZone,
+    {
+        let counter = FrameCounter::new(config.target_frame_rate, sampler);
+        let mut detector = HitchDetector::with_baseline(counter, config.hitch_baseline);
+        detector
+            .set_multiplier(config.hitch_multiplier)
+            .set_max_queued_events(0);
+
+        let mut frame_times = Vec::with_capacity(times.len());
+        let mut frame_rate_sum = 0.0;
+        for time in times {
+            detector.tick(time);
+            frame_rate_sum += detector.average_frame_rate();
+            frame_times.push(time.elapsed_wall_time().as_seconds());
+        }
+
+        let (mean, variance) = mean_and_variance(&frame_times);
+        let mut sorted = frame_times;
+        sorted.sort_by(f64::total_cmp);
+        let percentile = |q| if sorted.is_empty() {
+            FloatDuration::zero()
+        } else {
+            FloatDuration::seconds(sample::quantile(&sorted, q))
+        };
+
+        FrameStats {
+            num_frames: sorted.len(),
+            mean_frame_time: FloatDuration::seconds(mean),
+            std_dev_frame_time: FloatDuration::seconds(variance.sqrt()),
+            percentiles: PERCENTILES.iter().map(|&q| percentile(q)).collect(),
+            max_frame_time: percentile(1.0),
+            mean_frame_rate: frame_rate_sum / sorted.len() as f64,
+            hitch_count: detector.hitch_count(),
+        }
+    }
+
+    /// The number of frames in the recording.
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+    /// The mean frame time.
+    pub fn mean_frame_time(&self) -> FloatDuration {
+        self.mean_frame_time
+    }
+    /// The sample standard deviation of the frame time.
+    pub fn std_dev_frame_time(&self) -> FloatDuration {
+        self.std_dev_frame_time
+    }
+    /// The frame time at each of `PERCENTILES`.
+    pub fn percentiles(&self) -> &[FloatDuration] {
+        &self.percentiles
+    }
+    /// The 99th percentile frame time.
+    pub fn p99_frame_time(&self) -> FloatDuration {
+        self.percentiles[3]
+    }
+    /// The longest frame time.
+    pub fn max_frame_time(&self) -> FloatDuration {
+        self.max_frame_time
+    }
+    /// The mean of the average frame rates reported by the sampler.
+    pub fn mean_frame_rate(&self) -> f64 {
+        self.mean_frame_rate
+    }
+    /// The number of hitches detected.
+    pub fn hitch_count(&self) -> u64 {
+        self.hitch_count
+    }
+    /// The fraction of frames that were hitches.
+    pub fn hitch_rate(&self) -> f64 {
+        self.hitch_count as f64 / self.num_frames as f64
+    }
+}
+
+impl Comparison {
+    /// The statistics of the baseline recording.
+    pub fn baseline(&self) -> &FrameStats {
+        &self.baseline
+    }
+    /// The statistics of the candidate recording.
+    pub fn candidate(&self) -> &FrameStats {
+        &self.candidate
+    }
+    /// The fractional change in mean frame time from the baseline to the candidate.
+    pub fn mean_change(&self) -> f64 {
+        relative_change(self.baseline.mean_frame_time, self.candidate.mean_frame_time)
+    }
+    /// The fractional change in 99th percentile frame time.
+    pub fn p99_change(&self) -> f64 {
+        relative_change(self.baseline.p99_frame_time(), self.candidate.p99_frame_time())
+    }
+    /// Welch's t statistic for the difference in mean frame time.
+    ///
+    /// Positive values mean the candidate is slower.
+    pub fn t_statistic(&self) -> f64 {
+        self.t_statistic
+    }
+    /// The two-sided p-value of the difference in mean frame time.
+    pub fn p_value(&self) -> f64 {
+        self.p_value
+    }
+    /// Return true if the difference in mean frame time is statistically significant.
+    pub fn is_significant(&self) -> bool {
+        self.significant
+    }
+    /// Return true if any configured regression threshold was exceeded.
+    pub fn is_regression(&self) -> bool {
+        !self.regressions.is_empty()
+    }
+    /// Descriptions of each regression threshold that was exceeded.
+    pub fn regressions(&self) -> &[String] {
+        &self.regressions
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (base, cand) = (&self.baseline, &self.candidate);
+        writeln!(f, "{:<16}{:>14}{:>14}{:>10}", "", "baseline", "candidate", "change")?;
+        writeln!(f, "{:<16}{:>14}{:>14}", "frames", base.num_frames, cand.num_frames)?;
+        let ms_row = |f: &mut fmt::Formatter, name: &str, a: FloatDuration, b: FloatDuration| {
+            // The change from a baseline that rounds to zero, such as the deviation of a
+            // constant frame time, is meaningless.
+            let change = if a.as_milliseconds().abs() >= 0.0005 {
+                format!("{:+.1}%", relative_change(a, b) * 100.0)
+            } else {
+                "n/a".to_string()
+            };
+            writeln!(
+                f,
+                "{:<16}{:>11.3} ms{:>11.3} ms{:>10}",
+                name,
+                a.as_milliseconds(),
+                b.as_milliseconds(),
+                change
+            )
+        };
+        ms_row(f, "mean", base.mean_frame_time, cand.mean_frame_time)?;
+        ms_row(f, "std dev", base.std_dev_frame_time, cand.std_dev_frame_time)?;
+        for (i, q) in PERCENTILES.iter().enumerate() {
+            let name = format!("p{}", q * 100.0);
+            ms_row(f, &name, base.percentiles[i], cand.percentiles[i])?;
+        }
+        ms_row(f, "max", base.max_frame_time, cand.max_frame_time)?;
+        writeln!(
+            f,
+            "{:<16}{:>14.2}{:>14.2}",
+            "mean fps",
+            base.mean_frame_rate,
+            cand.mean_frame_rate
+        )?;
+        writeln!(f, "{:<16}{:>14}{:>14}", "hitches", base.hitch_count, cand.hitch_count)?;
+        writeln!(
+            f,
+            "t = {:.3}, p = {:.4} ({})",
+            self.t_statistic,
+            self.p_value,
+            if self.significant { "significant" } else { "not significant" }
+        )?;
+        if self.regressions.is_empty() {
+            write!(f, "no regression")
+        } else {
+            write!(f, "REGRESSION: {}", self.regressions.join("; "))
+        }
+    }
+}
+
+impl fmt::Display for CompareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CompareError::InsufficientData {
+                baseline_frames,
+                candidate_frames,
+            } => write!(
+                f,
+                "each recording must contain at least {} frames (baseline {}, candidate {})",
+                MIN_FRAMES,
+                baseline_frames,
+                candidate_frames
+            ),
+            CompareError::InvalidFrameTime {
+                recording,
+                frame_number,
+                seconds,
+            } => write!(
+                f,
+                "{} frame {} has an invalid elapsed wall time of {} seconds",
+                recording,
+                frame_number,
+                seconds
+            ),
+        }
+    }
+}
+
+impl Error for CompareError {}
+
+/// Compare a candidate recording against a baseline.
+///
+/// A clone of `sampler` is used to compute the frame rate of each recording. Returns an
+/// error if either recording has fewer than `MIN_FRAMES` frames, as the variance of the
+/// frame time, and so the significance of any difference, is then undefined. Frames with a
+/// negative or non-finite elapsed wall time are also rejected.
+pub fn compare<S, Tz>(
+    baseline: &[GameTime<Tz>],
+    candidate: &[GameTime<Tz>],
+    sampler: &S,
+    config: &CompareConfig,
+) -> Result<Comparison, CompareError>
+where
+    S: FrameRateSampler + Clone,
+    Tz: TimeZone,
+{
+    if baseline.len() < MIN_FRAMES || candidate.len() < MIN_FRAMES {
+        return Err(CompareError::InsufficientData {
+            baseline_frames: baseline.len(),
+            candidate_frames: candidate.len(),
+        });
+    }
+    check_frame_times("baseline", baseline)?;
+    check_frame_times("candidate", candidate)?;
+
+    let base = FrameStats::compute(baseline, sampler.clone(), config);
+    let cand = FrameStats::compute(candidate, sampler.clone(), config);
+    let (t_statistic, p_value) = welch_t_test(baseline, candidate);
+    let significant = p_value < config.significance_level;
+
+    let mut regressions = Vec::new();
+    let change = relative_change(base.mean_frame_time, cand.mean_frame_time);
+    if let Some(threshold) = config.mean_threshold {
+        if significant && change > threshold {
+            regressions.push(format!(
+                "mean frame time increased by {:.1}% (threshold {:.1}%)",
+                change * 100.0,
+                threshold * 100.0
+            ));
+        }
+    }
+    let p99_change = relative_change(base.p99_frame_time(), cand.p99_frame_time());
+    if let Some(threshold) = config.p99_threshold {
+        if p99_change > threshold {
+            regressions.push(format!(
+                "p99 frame time increased by {:.1}% (threshold {:.1}%)",
+                p99_change * 100.0,
+                threshold * 100.0
+            ));
+        }
+    }
+    let hitch_change = cand.hitch_rate() - base.hitch_rate();
+    if let Some(threshold) = config.hitch_rate_threshold {
+        if hitch_change > threshold {
+            regressions.push(format!(
+                "hitch rate increased by {:.2}% of frames (threshold {:.2}%)",
+                hitch_change * 100.0,
+                threshold * 100.0
+            ));
+        }
+    }
+
+    Ok(Comparison {
+        baseline: base,
+        candidate: cand,
+        t_statistic,
+        p_value,
+        significant,
+        regressions,
+    })
+}
+
+/// Return an error for the first frame of `times` with an unusable elapsed wall time.
+fn check_frame_times<Tz: TimeZone>(
+    recording: &'static str,
+    times: &[GameTime<Tz>],
+) -> Result<(), CompareError> {
+    match times.iter().find(|time| {
+        let seconds = time.elapsed_wall_time().as_seconds();
+        !seconds.is_finite() || seconds < 0.0
+    }) {
+        Some(time) => Err(CompareError::InvalidFrameTime {
+            recording,
+            frame_number: time.frame_number(),
+            seconds: time.elapsed_wall_time().as_seconds(),
+        }),
+        None => Ok(()),
+    }
+}
+
+fn relative_change(from: FloatDuration, to: FloatDuration) -> f64 {
+    (to.as_seconds() - from.as_seconds()) / from.as_seconds()
+}
+
+/// Return the mean and sample variance of `values`.
+fn mean_and_variance(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let sum_sq: f64 = values.iter().map(|x| (x - mean) * (x - mean)).sum();
+    let variance = if values.len() > 1 { sum_sq / (n - 1.0) } else { 0.0 };
+    (mean, variance)
+}
+
+/// Perform Welch's t-test on the wall frame times, returning the t statistic and
+/// two-sided p-value.
+///
+/// Both recordings must have at least `MIN_FRAMES` frames.
+fn welch_t_test<Tz>(baseline: &[GameTime<Tz>], candidate: &[GameTime<Tz>]) -> (f64, f64)
+where
+    Tz: TimeZone,
+{
+    debug_assert!(baseline.len() >= MIN_FRAMES && candidate.len() >= MIN_FRAMES);
+    let frame_times = |times: &[GameTime<Tz>]| -> Vec<f64> {
+        times
+            .iter()
+            .map(|t| t.elapsed_wall_time().as_seconds())
+            .collect()
+    };
+    let (mean1, var1) = mean_and_variance(&frame_times(baseline));
+    let (mean2, var2) = mean_and_variance(&frame_times(candidate));
+    let (n1, n2) = (baseline.len() as f64, candidate.len() as f64);
+
+    let se1 = var1 / n1;
+    let se2 = var2 / n2;
+    let diff = mean2 - mean1;
+    if se1 + se2 == 0.0 {
+        return if diff == 0.0 {
+            (0.0, 1.0)
+        } else {
+            (diff.signum() * f64::INFINITY, 0.0)
+        };
+    }
+
+    let t = diff / (se1 + se2).sqrt();
+    let df = (se1 + se2).powi(2) / (se1 * se1 / (n1 - 1.0) + se2 * se2 / (n2 - 1.0));
+    let p = regularized_incomplete_beta(df / 2.0, 0.5, df / (df + t * t));
+    (t, p)
+}
+
+/// The natural logarithm of the gamma function, by the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| sum + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * ::std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// The regularized incomplete beta function `I_x(a, b)`.
+fn regularized_incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    // The continued fraction converges quickly only below this point, so use the
+    // symmetry relation above it.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - ln_front.exp() * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/// Evaluate the continued fraction for the incomplete beta function by Lentz's method.
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const MAX_ITERATIONS: u32 = 300;
+    const EPSILON: f64 = 1e-14;
+    const TINY: f64 = 1e-300;
+
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+
+    for m in 1..MAX_ITERATIONS {
+        let m = m as f64;
+        for &numerator in &[
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono;
+    use clock::GameClock;
+    use framerate::LinearAverageSampler;
+    use step;
+
+    fn record(frame_us: &[i64]) -> Vec<GameTime> {
+        let mut clock = GameClock::new();
+        let step = step::VariableStep::new();
+        let mut frame_time = clock.start_wall_time();
+        frame_us
+            .iter()
+            .map(|&us| {
+                frame_time += chrono::Duration::microseconds(us);
+                clock.tick_with_wall_time(&step, frame_time)
+            })
+            .collect()
+    }
+
+    fn noisy_frames(base_us: i64, count: usize) -> Vec<i64> {
+        (0..count)
+            .map(|i| base_us + [0, 300, -300, 150, -150][i % 5])
+            .collect()
+    }
+
+    #[test]
+    fn test_t_distribution() {
+        // Known two-sided p-values of Student's t distribution.
+        assert!(relative_eq!(regularized_incomplete_beta(0.5, 0.5, 0.5), 0.5, epsilon = 1e-10));
+        let p = |t: f64, df: f64| regularized_incomplete_beta(df / 2.0, 0.5, df / (df + t * t));
+        assert!(relative_eq!(p(2.228, 10.0), 0.05, epsilon = 1e-4));
+        assert!(relative_eq!(p(1.96, 1e6), 0.05, epsilon = 1e-4));
+        assert!(relative_eq!(p(0.0, 5.0), 1.0, epsilon = 1e-10));
+    }
+
+    #[test]
+    fn test_frame_stats() {
+        let times = record(&[10_000, 10_000, 10_000, 10_000, 40_000]);
+        let config = CompareConfig::new();
+        let stats = FrameStats::compute(&times, LinearAverageSampler::new(), &config);
+
+        assert_eq!(stats.num_frames(), 5);
+        assert!(relative_eq!(stats.mean_frame_time().as_seconds(), 0.016, epsilon = 1e-9));
+        assert_eq!(stats.percentiles()[0], FloatDuration::milliseconds(10.0));
+        assert_eq!(stats.max_frame_time(), FloatDuration::milliseconds(40.0));
+        assert_eq!(stats.hitch_count(), 1);
+        assert!(relative_eq!(stats.hitch_rate(), 0.2, epsilon = 1e-9));
+    }
+
+    #[test]
+    fn test_compare() {
+        let sampler = LinearAverageSampler::new();
+        let config = CompareConfig::new();
+        let baseline = record(&noisy_frames(16_000, 500));
+
+        let same = compare(&baseline, &record(&noisy_frames(16_000, 500)), &sampler, &config)
+            .unwrap();
+        assert!(!same.is_significant());
+        assert!(!same.is_regression());
+        assert_eq!(same.mean_change(), 0.0);
+
+        let slower = record(&noisy_frames(17_600, 500));
+        let result = compare(&baseline, &slower, &sampler, &config).unwrap();
+        assert!(result.is_significant());
+        assert!(result.t_statistic() > 0.0);
+        assert!(result.is_regression());
+        assert!(relative_eq!(result.mean_change(), 0.1, epsilon = 1e-9));
+        assert!(result.candidate().mean_frame_rate() < result.baseline().mean_frame_rate());
+        assert!(result.to_string().contains("REGRESSION: mean frame time increased by 10.0%"));
+
+        let faster = compare(&slower, &baseline, &sampler, &config).unwrap();
+        assert!(faster.is_significant());
+        assert!(!faster.is_regression());
+
+        let mut strict = CompareConfig::new();
+        strict.mean_threshold(Some(0.2)).p99_threshold(Some(0.05));
+        let result = compare(&baseline, &slower, &sampler, &strict).unwrap();
+        assert_eq!(result.regressions().len(), 1);
+        assert!(result.regressions()[0].starts_with("p99"));
+    }
+
+    #[test]
+    fn test_insufficient_data() {
+        let sampler = LinearAverageSampler::new();
+        let config = CompareConfig::new();
+        let baseline = record(&noisy_frames(16_000, 100));
+
+        let insufficient = |baseline_frames, candidate_frames| {
+            Err(CompareError::InsufficientData {
+                baseline_frames,
+                candidate_frames,
+            })
+        };
+        assert_eq!(compare(&baseline, &[], &sampler, &config), insufficient(100, 0));
+
+        let single = record(&[40_000]);
+        assert_eq!(compare(&baseline, &single, &sampler, &config), insufficient(100, 1));
+        let err = compare(&single, &baseline, &sampler, &config).unwrap_err();
+        assert_eq!(err, insufficient(1, 100).unwrap_err());
+        assert!(err.to_string().contains("at least 2 frames"));
+
+        // Constant frame times have no deviation to compare against.
+        let constant = record(&[16_000; 10]);
+        let result = compare(&constant, &record(&[16_000, 17_000]), &sampler, &config).unwrap();
+        let report = result.to_string();
+        assert!(!report.contains("NaN"));
+        assert!(report.contains(" ms       n/a\n"));
+
+        let two = record(&[40_000, 40_100]);
+        let result = compare(&baseline, &two, &sampler, &config).unwrap();
+        assert!(result.p_value().is_finite());
+        assert!(result.is_regression());
+    }
+
+    #[test]
+    fn test_invalid_frame_time() {
+        let sampler = LinearAverageSampler::new();
+        let config = CompareConfig::new();
+        let baseline = record(&noisy_frames(16_000, 100));
+        let mut candidate = record(&noisy_frames(16_000, 100));
+        let time = candidate[10].clone();
+        candidate[10] = GameTime::from_parts(
+            time.frame_start_time(),
+            time.total_wall_time(),
+            time.total_game_time(),
+            time.elapsed_game_time(),
+            FloatDuration::seconds(f64::NAN),
+            time.frame_number(),
+            time.clock_multiplier(),
+        );
+
+        match compare(&baseline, &candidate, &sampler, &config) {
+            Err(CompareError::InvalidFrameTime {
+                recording,
+                frame_number,
+                seconds,
+            }) => {
+                assert_eq!(recording, "candidate");
+                assert_eq!(frame_number, 11);
+                assert!(seconds.is_nan());
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
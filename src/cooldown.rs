@@ -0,0 +1,334 @@
+//! Per-key cooldowns and timed effects measured in game time.
+//!
+//! [`Cooldowns`](./struct.Cooldowns.html) records when each cooldown started and how long
+//! it lasts, and answers queries against the total game time of the latest frame. Nothing
+//! is decremented per frame, so cooldowns follow the clock multiplier and do not progress
+//! while the game is paused.
+//!
+//! Cooldowns can be exported as plain [`SavedCooldown`](./struct.SavedCooldown.html) values
+//! for save games. These store the time remaining rather than absolute game times, so they
+//! can be restored against a clock that starts from a different game time.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use game_time::{GameClock, FloatDuration};
+//! use game_time::cooldown::Cooldowns;
+//! use game_time::step;
+//!
+//! let mut clock = GameClock::new();
+//! let mut cooldowns = Cooldowns::new();
+//! let step = step::ConstantStep::new(FloatDuration::seconds(1.0));
+//!
+//! cooldowns.tick(&clock.tick(&step));
+//! assert!(cooldowns.try_start("fireball", FloatDuration::seconds(3.0)));
+//! assert!(!cooldowns.try_start("fireball", FloatDuration::seconds(3.0)));
+//!
+//! cooldowns.tick(&clock.tick(&step));
+//! assert_eq!(cooldowns.remaining("fireball"), FloatDuration::seconds(2.0));
+//!
+//! for _ in 0..2 {
+//!     cooldowns.tick(&clock.tick(&step));
+//! }
+//! assert!(cooldowns.ready("fireball"));
+//! assert_eq!(cooldowns.drain_expired().collect::<Vec<_>>(), vec!["fireball"]);
+//! ```
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::vec::Drain;
+
+use chrono::TimeZone;
+use float_duration::FloatDuration;
+
+use clock::GameTime;
+
+/// A cooldown in a form suitable for save games.
+///
+/// Times are stored in seconds of game time, relative to the time the cooldowns were saved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedCooldown<K> {
+    /// The key of the cooldown.
+    pub key: K,
+    /// The game time remaining until the cooldown expires, in seconds.
+    pub remaining_seconds: f64,
+    /// The full duration of the cooldown, in seconds.
+    pub duration_seconds: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Cooldown {
+    start: FloatDuration,
+    duration: FloatDuration,
+}
+
+/// A set of cooldowns keyed by `K`.
+///
+/// Call [`tick`](#method.tick) at the start of every frame to update the current game time.
+/// A key without a running cooldown is always ready.
+#[derive(Debug, Clone)]
+pub struct Cooldowns<K: Eq + Hash> {
+    cooldowns: HashMap<K, Cooldown>,
+    now: FloatDuration,
+    expired: Vec<K>,
+}
+
+impl Cooldown {
+    fn end(&self) -> FloatDuration {
+        self.start + self.duration
+    }
+}
+
+impl<K: Eq + Hash> Cooldowns<K> {
+    /// Construct a new, empty `Cooldowns`.
+    pub fn new() -> Cooldowns<K> {
+        Cooldowns {
+            cooldowns: HashMap::new(),
+            now: FloatDuration::zero(),
+            expired: Vec::new(),
+        }
+    }
+
+    /// Update the current game time to that of the frame `time`.
+    pub fn tick<Tz: TimeZone>(&mut self, time: &GameTime<Tz>) {
+        self.now = time.total_game_time();
+    }
+    /// Return the current game time.
+    pub fn now(&self) -> FloatDuration {
+        self.now
+    }
+
+    /// Start a cooldown for `key` lasting `duration`, replacing any running cooldown.
+    pub fn start(&mut self, key: K, duration: FloatDuration) {
+        let cooldown = Cooldown {
+            start: self.now,
+            duration,
+        };
+        self.cooldowns.insert(key, cooldown);
+    }
+    /// Start a cooldown for `key` lasting `duration` if `key` is ready.
+    ///
+    /// Returns true if the cooldown was started.
+    pub fn try_start(&mut self, key: K, duration: FloatDuration) -> bool {
+        if self.ready(&key) {
+            self.start(key, duration);
+            true
+        } else {
+            false
+        }
+    }
+    /// Extend the running cooldown for `key` by `extra`.
+    ///
+    /// Returns false if `key` is ready.
+    pub fn extend<Q>(&mut self, key: &Q, extra: FloatDuration) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let now = self.now;
+        match self.cooldowns.get_mut(key) {
+            Some(cooldown) if cooldown.end() > now => {
+                cooldown.duration += extra;
+                true
+            }
+            _ => false,
+        }
+    }
+    /// Cancel the cooldown for `key`, making it ready.
+    ///
+    /// Returns false if `key` was already ready.
+    pub fn cancel<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let now = self.now;
+        match self.cooldowns.remove(key) {
+            Some(cooldown) => cooldown.end() > now,
+            None => false,
+        }
+    }
+
+    /// Return true if `key` has no running cooldown.
+    pub fn ready<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.remaining(key) <= FloatDuration::zero()
+    }
+    /// Return the game time remaining on the cooldown for `key`, or zero if it is ready.
+    pub fn remaining<Q>(&self, key: &Q) -> FloatDuration
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        match self.cooldowns.get(key) {
+            Some(cooldown) => (cooldown.end() - self.now).max(FloatDuration::zero()),
+            None => FloatDuration::zero(),
+        }
+    }
+    /// Return the fraction of the cooldown for `key` that has elapsed, from `0.0` when it
+    /// starts to `1.0` when it is ready.
+    pub fn fraction<Q>(&self, key: &Q) -> f64
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        match self.cooldowns.get(key) {
+            Some(cooldown) if !cooldown.duration.is_zero() => {
+                ((self.now - cooldown.start) / cooldown.duration).clamp(0.0, 1.0)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Remove all expired cooldowns, returning their keys.
+    ///
+    /// The order of the keys is unspecified.
+    pub fn drain_expired(&mut self) -> Drain<'_, K>
+    where
+        K: Clone,
+    {
+        let now = self.now;
+        let expired = &mut self.expired;
+        self.cooldowns.retain(|key, cooldown| {
+            let running = cooldown.end() > now;
+            if !running {
+                expired.push(key.clone());
+            }
+            running
+        });
+        self.expired.drain(..)
+    }
+    /// Return the number of tracked cooldowns, including expired ones not yet drained.
+    pub fn len(&self) -> usize {
+        self.cooldowns.len()
+    }
+    /// Return true if no cooldowns are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.cooldowns.is_empty()
+    }
+    /// Remove all cooldowns.
+    pub fn clear(&mut self) {
+        self.cooldowns.clear();
+    }
+
+    /// Export all running cooldowns for a save game.
+    pub fn save(&self) -> Vec<SavedCooldown<K>>
+    where
+        K: Clone,
+    {
+        self.cooldowns
+            .iter()
+            .filter(|&(_, cooldown)| cooldown.end() > self.now)
+            .map(|(key, cooldown)| SavedCooldown {
+                key: key.clone(),
+                remaining_seconds: (cooldown.end() - self.now).as_seconds(),
+                duration_seconds: cooldown.duration.as_seconds(),
+            })
+            .collect()
+    }
+    /// Restore cooldowns exported by [`save`](#method.save), relative to the current game time.
+    ///
+    /// Restored cooldowns replace any running cooldowns with the same key.
+    pub fn load<I>(&mut self, saved: I)
+    where
+        I: IntoIterator<Item = SavedCooldown<K>>,
+    {
+        for cooldown in saved {
+            let duration = FloatDuration::seconds(cooldown.duration_seconds);
+            let remaining = FloatDuration::seconds(cooldown.remaining_seconds);
+            let restored = Cooldown {
+                start: self.now + remaining - duration,
+                duration,
+            };
+            self.cooldowns.insert(cooldown.key, restored);
+        }
+    }
+}
+
+impl<K: Eq + Hash> Default for Cooldowns<K> {
+    fn default() -> Cooldowns<K> {
+        Cooldowns::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::{GameClock, GameClockBuilder};
+    use step;
+
+    #[test]
+    fn test_cooldowns() {
+        let mut clock = GameClock::new();
+        let step = step::ConstantStep::new(FloatDuration::seconds(1.0));
+        let mut cooldowns = Cooldowns::new();
+
+        cooldowns.start("dash".to_string(), FloatDuration::seconds(4.0));
+        assert!(!cooldowns.ready("dash"));
+        assert!(cooldowns.ready("jump"));
+        assert_eq!(cooldowns.fraction("dash"), 0.0);
+        assert_eq!(cooldowns.fraction("jump"), 1.0);
+
+        cooldowns.tick(&clock.tick(&step));
+        assert_eq!(cooldowns.remaining("dash"), FloatDuration::seconds(3.0));
+        assert_eq!(cooldowns.fraction("dash"), 0.25);
+        assert!(cooldowns.extend("dash", FloatDuration::seconds(2.0)));
+        assert!(!cooldowns.extend("jump", FloatDuration::seconds(2.0)));
+        assert_eq!(cooldowns.remaining("dash"), FloatDuration::seconds(5.0));
+
+        // Pausing the clock pauses the cooldown.
+        clock.set_clock_multiplier(0.0);
+        cooldowns.tick(&clock.tick(&step));
+        assert_eq!(cooldowns.remaining("dash"), FloatDuration::seconds(5.0));
+
+        clock.set_clock_multiplier(5.0);
+        cooldowns.tick(&clock.tick(&step));
+        assert!(cooldowns.ready("dash"));
+        assert_eq!(cooldowns.remaining("dash"), FloatDuration::zero());
+        assert_eq!(cooldowns.len(), 1);
+        assert_eq!(cooldowns.drain_expired().collect::<Vec<_>>(), vec!["dash"]);
+        assert!(cooldowns.is_empty());
+
+        cooldowns.start("dash".to_string(), FloatDuration::seconds(1.0));
+        assert!(cooldowns.cancel("dash"));
+        assert!(!cooldowns.cancel("dash"));
+        assert!(cooldowns.ready("dash"));
+    }
+
+    #[test]
+    fn test_save_load() {
+        let mut clock = GameClock::new();
+        let step = step::ConstantStep::new(FloatDuration::seconds(1.0));
+        let mut cooldowns = Cooldowns::new();
+        cooldowns.start(1, FloatDuration::seconds(10.0));
+        cooldowns.start(2, FloatDuration::seconds(1.0));
+        for _ in 0..4 {
+            cooldowns.tick(&clock.tick(&step));
+        }
+
+        let saved = cooldowns.save();
+        assert_eq!(
+            saved,
+            vec![
+                SavedCooldown {
+                    key: 1,
+                    remaining_seconds: 6.0,
+                    duration_seconds: 10.0,
+                },
+            ]
+        );
+
+        let clock = GameClockBuilder::new()
+            .start_game_time(::std::time::Duration::new(100, 0))
+            .build();
+        let mut restored = Cooldowns::new();
+        restored.tick(clock.last_frame_time());
+        restored.load(saved);
+        assert_eq!(restored.remaining(&1), FloatDuration::seconds(6.0));
+        assert_eq!(restored.fraction(&1), 0.4);
+        assert!(restored.ready(&2));
+    }
+}
@@ -0,0 +1,387 @@
+//! A day/night cycle driven by game time.
+//!
+//! [`DayCycle`](./struct.DayCycle.html) reports a continuous time-of-day phase along with
+//! a sun elevation curve and the named [`DayPeriod`](./enum.DayPeriod.html) of the day.
+//! Calling [`tick`](./struct.DayCycle.html#method.tick) with each frame's `GameTime` reports
+//! every period transition passed during the frame. As the cycle follows the total game time,
+//! it speeds up with the clock multiplier and stops while the game is paused.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use game_time::{GameClock, FloatDuration};
+//! use game_time::daycycle::{DayCycle, DayPeriod};
+//! use game_time::step;
+//!
+//! let mut cycle = DayCycle::new(FloatDuration::minutes(24.0));
+//! let mut clock = GameClock::new();
+//! let step = step::ConstantStep::new(FloatDuration::seconds(30.0));
+//!
+//! // Skip ahead through the night at ten times the speed.
+//! clock.set_clock_multiplier(10.0);
+//! cycle.tick(clock.last_frame_time());
+//! let time = clock.tick(&step);
+//!
+//! let transitions = cycle.tick(&time);
+//! assert_eq!(transitions.len(), 1);
+//! assert_eq!(transitions[0].to(), DayPeriod::Dawn);
+//! assert_eq!(cycle.period(), DayPeriod::Dawn);
+//! assert!(cycle.sun_elevation() < 0.0);
+//! ```
+use std::f64::consts::PI;
+
+use chrono::TimeZone;
+use float_duration::FloatDuration;
+
+use calendar::GameCalendar;
+use clock::GameTime;
+
+/// The default phase at which dawn starts, 04:48 on a 24 hour day.
+pub const DEFAULT_DAWN_START: f64 = 0.2;
+/// The default fraction of the day taken by each of dawn and dusk.
+pub const DEFAULT_TWILIGHT_LENGTH: f64 = 0.1;
+/// The default fraction of the day taken by the day period.
+pub const DEFAULT_DAYTIME_LENGTH: f64 = 0.4;
+
+/// A named period of the day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DayPeriod {
+    /// The transition from night to day, centered on sunrise.
+    Dawn,
+    /// The time between dawn and dusk.
+    Day,
+    /// The transition from day to night, centered on sunset.
+    Dusk,
+    /// The time between dusk and dawn.
+    Night,
+}
+
+/// A change of period passed during a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeriodTransition {
+    from: DayPeriod,
+    to: DayPeriod,
+    game_time: FloatDuration,
+}
+
+/// A day/night cycle following the total game time.
+///
+/// The phase of the cycle runs from `0.0` at midnight to `1.0` at the next midnight. The
+/// day is divided into dawn, day, dusk and night, in that order, with the night taking
+/// the remainder of the day. Sunrise and sunset are at the midpoints of dawn and dusk.
+#[derive(Debug, Clone)]
+pub struct DayCycle {
+    day_length: FloatDuration,
+    offset: FloatDuration,
+    dawn_start: f64,
+    dawn_length: f64,
+    daytime_length: f64,
+    dusk_length: f64,
+    position: Option<f64>,
+    transitions: Vec<PeriodTransition>,
+}
+
+impl PeriodTransition {
+    /// The period before the transition.
+    pub fn from(&self) -> DayPeriod {
+        self.from
+    }
+    /// The period after the transition.
+    pub fn to(&self) -> DayPeriod {
+        self.to
+    }
+    /// The total game time at which the transition happened.
+    pub fn game_time(&self) -> FloatDuration {
+        self.game_time
+    }
+}
+
+impl DayCycle {
+    /// Construct a new `DayCycle` where each day lasts `day_length` of game time.
+    ///
+    /// Zero game time is at midnight.
+    pub fn new(day_length: FloatDuration) -> DayCycle {
+        DayCycle {
+            day_length,
+            offset: FloatDuration::zero(),
+            dawn_start: DEFAULT_DAWN_START,
+            dawn_length: DEFAULT_TWILIGHT_LENGTH,
+            daytime_length: DEFAULT_DAYTIME_LENGTH,
+            dusk_length: DEFAULT_TWILIGHT_LENGTH,
+            position: None,
+            transitions: Vec::new(),
+        }
+    }
+    /// Construct a new `DayCycle` matching the day length and epoch of `calendar`.
+    pub fn from_calendar(calendar: &GameCalendar) -> DayCycle {
+        let mut cycle = DayCycle::new(calendar.day_length());
+        cycle.set_offset(calendar.epoch());
+        cycle
+    }
+
+    /// Return the game time taken by each day.
+    pub fn day_length(&self) -> FloatDuration {
+        self.day_length
+    }
+    /// Set the game time taken by each day.
+    pub fn set_day_length(&mut self, day_length: FloatDuration) -> &mut DayCycle {
+        self.day_length = day_length;
+        self
+    }
+    /// Return the time of day at zero game time, measured in game time.
+    pub fn offset(&self) -> FloatDuration {
+        self.offset
+    }
+    /// Set the time of day at zero game time, measured in game time.
+    pub fn set_offset(&mut self, offset: FloatDuration) -> &mut DayCycle {
+        self.offset = offset;
+        self
+    }
+    /// Return the phase at which dawn starts.
+    pub fn dawn_start(&self) -> f64 {
+        self.dawn_start
+    }
+    /// Set the phase at which dawn starts.
+    pub fn set_dawn_start(&mut self, phase: f64) -> &mut DayCycle {
+        self.dawn_start = phase.rem_euclid(1.0);
+        self
+    }
+    /// Return the fraction of the day taken by `period`.
+    pub fn period_length(&self, period: DayPeriod) -> f64 {
+        match period {
+            DayPeriod::Dawn => self.dawn_length,
+            DayPeriod::Day => self.daytime_length,
+            DayPeriod::Dusk => self.dusk_length,
+            DayPeriod::Night => 1.0 - self.dawn_length - self.daytime_length - self.dusk_length,
+        }
+    }
+    /// Set the fractions of the day taken by dawn, day and dusk.
+    ///
+    /// The night takes the remainder of the day, so the lengths must sum to less than `1.0`.
+    pub fn set_period_lengths(&mut self, dawn: f64, day: f64, dusk: f64) -> &mut DayCycle {
+        self.dawn_length = dawn;
+        self.daytime_length = day;
+        self.dusk_length = dusk;
+        self
+    }
+
+    /// Return the phase of the day at the total game time `game_time`.
+    pub fn phase_at(&self, game_time: FloatDuration) -> f64 {
+        self.cycle_position(game_time).rem_euclid(1.0)
+    }
+    /// Return the period of the day at `phase`.
+    pub fn period_at(&self, phase: f64) -> DayPeriod {
+        let since_dawn = (phase - self.dawn_start).rem_euclid(1.0);
+        if since_dawn < self.dawn_length {
+            DayPeriod::Dawn
+        } else if since_dawn < self.dawn_length + self.daytime_length {
+            DayPeriod::Day
+        } else if since_dawn < self.dawn_length + self.daytime_length + self.dusk_length {
+            DayPeriod::Dusk
+        } else {
+            DayPeriod::Night
+        }
+    }
+    /// Return the elevation of the sun at `phase`, from `-1.0` to `1.0`.
+    ///
+    /// The elevation is zero at sunrise and sunset, and follows a half sine wave across
+    /// the daylight hours up to `1.0`, and across the night down to `-1.0`.
+    pub fn sun_elevation_at(&self, phase: f64) -> f64 {
+        let sunrise = self.dawn_start + self.dawn_length / 2.0;
+        let daylight = self.dawn_length / 2.0 + self.daytime_length + self.dusk_length / 2.0;
+        let since_sunrise = (phase - sunrise).rem_euclid(1.0);
+        if since_sunrise < daylight {
+            (PI * since_sunrise / daylight).sin()
+        } else {
+            -(PI * (since_sunrise - daylight) / (1.0 - daylight)).sin()
+        }
+    }
+    /// Return the amount of daylight at `phase`, from `0.0` at night to `1.0` during the day.
+    ///
+    /// The daylight rises smoothly through dawn and falls through dusk.
+    pub fn daylight_at(&self, phase: f64) -> f64 {
+        let since_dawn = (phase - self.dawn_start).rem_euclid(1.0);
+        let dusk_start = self.dawn_length + self.daytime_length;
+        match self.period_at(phase) {
+            DayPeriod::Dawn => smoothstep(since_dawn / self.dawn_length),
+            DayPeriod::Day => 1.0,
+            DayPeriod::Dusk => 1.0 - smoothstep((since_dawn - dusk_start) / self.dusk_length),
+            DayPeriod::Night => 0.0,
+        }
+    }
+
+    /// Update the cycle for a new frame, returning the period transitions passed since the
+    /// previous frame in order.
+    ///
+    /// No transitions are reported for the first frame.
+    pub fn tick<Tz: TimeZone>(&mut self, time: &GameTime<Tz>) -> &[PeriodTransition] {
+        self.transitions.clear();
+        let position = self.cycle_position(time.total_game_time());
+        if let Some(previous) = self.position {
+            if position > previous {
+                self.record_transitions(previous, position);
+            }
+        }
+        self.position = Some(position);
+        &self.transitions
+    }
+    /// Return the transitions reported by the last call to `tick`.
+    pub fn transitions(&self) -> &[PeriodTransition] {
+        &self.transitions
+    }
+    /// Return the number of whole days elapsed as of the last frame.
+    pub fn day(&self) -> u64 {
+        self.position.unwrap_or(0.0).max(0.0).floor() as u64
+    }
+    /// Return the phase of the day as of the last frame.
+    pub fn phase(&self) -> f64 {
+        self.position.unwrap_or(0.0).rem_euclid(1.0)
+    }
+    /// Return the period of the day as of the last frame.
+    pub fn period(&self) -> DayPeriod {
+        self.period_at(self.phase())
+    }
+    /// Return the elevation of the sun as of the last frame.
+    pub fn sun_elevation(&self) -> f64 {
+        self.sun_elevation_at(self.phase())
+    }
+    /// Return the amount of daylight as of the last frame.
+    pub fn daylight(&self) -> f64 {
+        self.daylight_at(self.phase())
+    }
+
+    fn cycle_position(&self, game_time: FloatDuration) -> f64 {
+        (game_time + self.offset) / self.day_length
+    }
+
+    fn record_transitions(&mut self, start: f64, end: f64) {
+        let mut boundaries = [
+            (self.dawn_start, DayPeriod::Night, DayPeriod::Dawn),
+            (self.dawn_start + self.dawn_length, DayPeriod::Dawn, DayPeriod::Day),
+            (
+                self.dawn_start + self.dawn_length + self.daytime_length,
+                DayPeriod::Day,
+                DayPeriod::Dusk,
+            ),
+            (
+                self.dawn_start + self.dawn_length + self.daytime_length + self.dusk_length,
+                DayPeriod::Dusk,
+                DayPeriod::Night,
+            ),
+        ];
+        for boundary in &mut boundaries {
+            boundary.0 = boundary.0.rem_euclid(1.0);
+        }
+        boundaries.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let mut day = start.floor();
+        while day <= end.floor() {
+            for &(phase, from, to) in &boundaries {
+                let position = day + phase;
+                if position > start && position <= end {
+                    self.transitions.push(PeriodTransition {
+                        from,
+                        to,
+                        game_time: self.day_length * position - self.offset,
+                    });
+                }
+            }
+            day += 1.0;
+        }
+    }
+}
+
+fn smoothstep(x: f64) -> f64 {
+    let x = x.clamp(0.0, 1.0);
+    x * x * (3.0 - 2.0 * x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::GameClock;
+    use step;
+
+    #[test]
+    fn test_periods() {
+        let cycle = DayCycle::new(FloatDuration::minutes(24.0));
+        assert_eq!(cycle.period_at(0.0), DayPeriod::Night);
+        assert_eq!(cycle.period_at(0.25), DayPeriod::Dawn);
+        assert_eq!(cycle.period_at(0.5), DayPeriod::Day);
+        assert_eq!(cycle.period_at(0.75), DayPeriod::Dusk);
+        assert_eq!(cycle.period_at(0.9), DayPeriod::Night);
+        assert!(relative_eq!(cycle.period_length(DayPeriod::Night), 0.4, epsilon = 1e-9));
+
+        assert!(relative_eq!(cycle.sun_elevation_at(0.25), 0.0, epsilon = 1e-9));
+        assert!(relative_eq!(cycle.sun_elevation_at(0.5), 1.0, epsilon = 1e-9));
+        assert!(relative_eq!(cycle.sun_elevation_at(0.75), 0.0, epsilon = 1e-9));
+        assert!(relative_eq!(cycle.sun_elevation_at(0.0), -1.0, epsilon = 1e-9));
+
+        assert_eq!(cycle.daylight_at(0.1), 0.0);
+        assert!(relative_eq!(cycle.daylight_at(0.25), 0.5, epsilon = 1e-9));
+        assert_eq!(cycle.daylight_at(0.5), 1.0);
+        assert!(relative_eq!(cycle.daylight_at(0.75), 0.5, epsilon = 1e-9));
+
+        assert!(relative_eq!(
+            cycle.phase_at(FloatDuration::minutes(24.0 * 3.0 + 6.0)),
+            0.25,
+            epsilon = 1e-9
+        ));
+    }
+
+    #[test]
+    fn test_transitions() {
+        let mut cycle = DayCycle::new(FloatDuration::minutes(24.0));
+        let mut clock = GameClock::new();
+        let step = step::ConstantStep::new(FloatDuration::minutes(1.0));
+
+        assert!(cycle.tick(clock.last_frame_time()).is_empty());
+        let mut periods = Vec::new();
+        for _ in 0..24 {
+            let time = clock.tick(&step);
+            for transition in cycle.tick(&time) {
+                assert!(transition.game_time() <= time.total_game_time());
+                periods.push(transition.to());
+            }
+        }
+        assert_eq!(
+            periods,
+            vec![DayPeriod::Dawn, DayPeriod::Day, DayPeriod::Dusk, DayPeriod::Night]
+        );
+        assert_eq!(cycle.day(), 1);
+
+        // A paused clock does not move the sun.
+        clock.set_clock_multiplier(0.0);
+        let time = clock.tick(&step);
+        assert!(cycle.tick(&time).is_empty());
+
+        // A time skip reports every transition passed, in order.
+        clock.set_clock_multiplier(30.0);
+        let time = clock.tick(&step);
+        let transitions = cycle.tick(&time).to_vec();
+        assert_eq!(transitions.len(), 5);
+        assert_eq!(transitions[0].from(), DayPeriod::Night);
+        assert_eq!(transitions[0].to(), DayPeriod::Dawn);
+        assert!(relative_eq!(
+            transitions[0].game_time().as_seconds(),
+            FloatDuration::minutes(24.0 * 1.2).as_seconds(),
+            epsilon = 1e-6
+        ));
+        assert_eq!(transitions[4].to(), DayPeriod::Dawn);
+        assert_eq!(cycle.day(), 2);
+        assert_eq!(cycle.period(), DayPeriod::Dawn);
+    }
+
+    #[test]
+    fn test_from_calendar() {
+        let mut calendar = GameCalendar::new(FloatDuration::minutes(24.0));
+        let epoch = calendar.hour_length() * 12.0;
+        calendar.set_epoch(epoch);
+
+        let mut cycle = DayCycle::from_calendar(&calendar);
+        cycle.set_period_lengths(0.05, 0.5, 0.05).set_dawn_start(0.2);
+        assert!(relative_eq!(cycle.phase_at(FloatDuration::zero()), 0.5, epsilon = 1e-9));
+        assert_eq!(cycle.period_at(0.74), DayPeriod::Day);
+        assert_eq!(cycle.period_at(0.76), DayPeriod::Dusk);
+    }
+}
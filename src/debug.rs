@@ -0,0 +1,362 @@
+//! Editor-style controls for pausing and stepping a simulation.
+//!
+//! [`DebugController`](./struct.DebugController.html) wraps a
+//! [`FrameRunner`](../runner/struct.FrameRunner.html) and decides, once per update, whether
+//! the simulation runs a frame. While playing, frames use the time step passed to
+//! [`update`](./struct.DebugController.html#method.update). While stepping, every frame uses
+//! the controller's own debug time step, so each step advances game time by the same amount
+//! no matter how much wall time passed between steps.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use game_time::{GameClock, FrameCounter, FloatDuration};
+//! use game_time::debug::{DebugController, DebugState};
+//! use game_time::framerate::LinearAverageSampler;
+//! use game_time::runner::FrameRunner;
+//! use game_time::step;
+//!
+//! let counter = FrameCounter::new(60.0, LinearAverageSampler::new());
+//! let runner = FrameRunner::new(GameClock::new(), counter);
+//! let debug_step = step::ConstantStep::new(FloatDuration::seconds(1.0 / 60.0));
+//! let mut controller = DebugController::new(runner, debug_step);
+//! let play_step = step::VariableStep::new();
+//!
+//! controller.pause();
+//! assert!(controller.update(&play_step).is_none());
+//!
+//! controller.step_frames(2);
+//! assert!(controller.update(&play_step).is_some());
+//! assert!(controller.update(&play_step).is_some());
+//! assert_eq!(controller.state(), DebugState::Paused);
+//! assert_eq!(controller.runner().clock().current_frame_number(), 2);
+//! ```
+use std::fmt;
+
+use chrono::{self, TimeZone};
+use float_duration::FloatDuration;
+
+use clock::GameTime;
+use framerate::FrameCount;
+use runner::FrameRunner;
+use step::{StatefulStep, TimeStep};
+
+/// Remaining game time below which a timed step is considered complete.
+///
+/// This absorbs rounding error when the requested duration is a multiple of the debug step.
+const STEP_FOR_EPSILON: f64 = 1e-9;
+
+/// The state of a [`DebugController`](./struct.DebugController.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugState {
+    /// Frames run normally.
+    Playing,
+    /// No frames run.
+    Paused,
+    /// This many more frames run with the debug step, then the controller pauses.
+    SteppingFrames(u64),
+    /// Frames run with the debug step until this much more game time has passed, then the
+    /// controller pauses.
+    SteppingFor(FloatDuration),
+    /// Frames run normally until the frame with this number, then the controller pauses.
+    RunningUntil(u64),
+}
+
+type StateCallback = Box<dyn FnMut(DebugState, DebugState)>;
+
+/// Play, pause and frame stepping controls around a `FrameRunner`.
+///
+/// The controller starts in the `Playing` state. Call [`update`](#method.update) once per
+/// frame of the host application; it returns the `GameTime` of the simulation frame that
+/// was run, if any.
+///
+/// When resuming play with a variable time step, the first frame includes all wall time
+/// spent paused. Use a step that limits the elapsed time if this is undesirable.
+pub struct DebugController<C: FrameCount, S: TimeStep, Tz: TimeZone = chrono::Local> {
+    runner: FrameRunner<C, Tz>,
+    debug_step: S,
+    state: DebugState,
+    callbacks: Vec<StateCallback>,
+}
+
+impl<C: FrameCount, S: TimeStep, Tz: TimeZone> DebugController<C, S, Tz> {
+    /// Construct a new `DebugController` stepping with `debug_step`.
+    pub fn new(runner: FrameRunner<C, Tz>, debug_step: S) -> DebugController<C, S, Tz> {
+        DebugController {
+            runner,
+            debug_step,
+            state: DebugState::Playing,
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Get a reference to the contained `FrameRunner`.
+    pub fn runner(&self) -> &FrameRunner<C, Tz> {
+        &self.runner
+    }
+    /// Get a mutable reference to the contained `FrameRunner`.
+    pub fn runner_mut(&mut self) -> &mut FrameRunner<C, Tz> {
+        &mut self.runner
+    }
+    /// Consume the `DebugController`, returning the contained `FrameRunner`.
+    pub fn into_runner(self) -> FrameRunner<C, Tz> {
+        self.runner
+    }
+    /// Get a reference to the time step used while stepping.
+    pub fn debug_step(&self) -> &S {
+        &self.debug_step
+    }
+    /// Set the time step used while stepping.
+    pub fn set_debug_step(&mut self, step: S) -> &mut DebugController<C, S, Tz> {
+        self.debug_step = step;
+        self
+    }
+
+    /// Return the current state.
+    pub fn state(&self) -> DebugState {
+        self.state
+    }
+    /// Return true if the controller is paused.
+    pub fn is_paused(&self) -> bool {
+        self.state == DebugState::Paused
+    }
+    /// Register a callback invoked with the old and new state whenever the state changes.
+    pub fn on_state_change<F>(&mut self, callback: F) -> &mut DebugController<C, S, Tz>
+    where
+        F: FnMut(DebugState, DebugState) + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Run frames normally.
+    pub fn play(&mut self) {
+        self.set_state(DebugState::Playing);
+    }
+    /// Stop running frames.
+    pub fn pause(&mut self) {
+        self.set_state(DebugState::Paused);
+    }
+    /// Pause if running frames, otherwise play.
+    pub fn toggle_pause(&mut self) {
+        if self.is_paused() {
+            self.play();
+        } else {
+            self.pause();
+        }
+    }
+    /// Run a single frame with the debug step, then pause.
+    pub fn step(&mut self) {
+        self.step_frames(1);
+    }
+    /// Run `frames` frames with the debug step, then pause.
+    pub fn step_frames(&mut self, frames: u64) {
+        if frames == 0 {
+            self.pause();
+        } else {
+            self.set_state(DebugState::SteppingFrames(frames));
+        }
+    }
+    /// Run frames with the debug step until at least `duration` of game time has passed,
+    /// then pause.
+    pub fn step_for(&mut self, duration: FloatDuration) {
+        if duration.as_seconds() <= STEP_FOR_EPSILON {
+            self.pause();
+        } else {
+            self.set_state(DebugState::SteppingFor(duration));
+        }
+    }
+    /// Run frames normally until the frame numbered `frame_number`, then pause.
+    ///
+    /// Pauses immediately if that frame has already been run.
+    pub fn run_until_frame(&mut self, frame_number: u64) {
+        if self.runner.clock().current_frame_number() >= frame_number {
+            self.pause();
+        } else {
+            self.set_state(DebugState::RunningUntil(frame_number));
+        }
+    }
+
+    /// Run a frame if the current state calls for one.
+    ///
+    /// `play_step` is used when playing or running until a frame. Returns the `GameTime`
+    /// of the frame, or `None` if paused.
+    pub fn update<T: StatefulStep>(&mut self, play_step: T) -> Option<GameTime<Tz>> {
+        self.update_with(|runner, debug_step| match debug_step {
+            Some(step) => runner.tick(step),
+            None => runner.tick(play_step),
+        })
+    }
+    /// Run a frame with a specified wall time if the current state calls for one.
+    ///
+    /// This function is like `update` but allows for the start time for the
+    /// frame to be specified.
+    pub fn update_with_wall_time<T: StatefulStep>(
+        &mut self,
+        play_step: T,
+        frame_start: chrono::DateTime<Tz>,
+    ) -> Option<GameTime<Tz>> {
+        self.update_with(|runner, debug_step| match debug_step {
+            Some(step) => runner.tick_with_wall_time(step, frame_start),
+            None => runner.tick_with_wall_time(play_step, frame_start),
+        })
+    }
+
+    fn update_with<F>(&mut self, tick: F) -> Option<GameTime<Tz>>
+    where
+        F: FnOnce(&mut FrameRunner<C, Tz>, Option<&S>) -> GameTime<Tz>,
+    {
+        let (time, next_state) = match self.state {
+            DebugState::Paused => return None,
+            DebugState::Playing => (tick(&mut self.runner, None), DebugState::Playing),
+            DebugState::RunningUntil(frame) => {
+                let time = tick(&mut self.runner, None);
+                if time.frame_number() >= frame {
+                    (time, DebugState::Paused)
+                } else {
+                    (time, DebugState::RunningUntil(frame))
+                }
+            }
+            DebugState::SteppingFrames(frames) => {
+                let time = tick(&mut self.runner, Some(&self.debug_step));
+                if frames <= 1 {
+                    (time, DebugState::Paused)
+                } else {
+                    (time, DebugState::SteppingFrames(frames - 1))
+                }
+            }
+            DebugState::SteppingFor(remaining) => {
+                let time = tick(&mut self.runner, Some(&self.debug_step));
+                let remaining = remaining - time.elapsed_game_time();
+                // Stop if game time is not advancing, as the step would never finish.
+                if remaining.as_seconds() <= STEP_FOR_EPSILON ||
+                    time.elapsed_game_time().as_seconds() <= 0.0
+                {
+                    (time, DebugState::Paused)
+                } else {
+                    (time, DebugState::SteppingFor(remaining))
+                }
+            }
+        };
+        self.set_state(next_state);
+        Some(time)
+    }
+
+    fn set_state(&mut self, state: DebugState) {
+        let old_state = self.state;
+        self.state = state;
+        if old_state != state {
+            for callback in &mut self.callbacks {
+                callback(old_state, state);
+            }
+        }
+    }
+}
+
+impl<C, S, Tz> fmt::Debug for DebugController<C, S, Tz>
+where
+    C: FrameCount,
+    S: TimeStep + fmt::Debug,
+    Tz: TimeZone + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DebugController")
+            .field("runner", &self.runner)
+            .field("debug_step", &self.debug_step)
+            .field("state", &self.state)
+            .field("callbacks", &self.callbacks.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use clock::GameClock;
+    use framerate::{FrameCounter, LinearAverageSampler};
+    use step;
+
+    fn controller() -> DebugController<FrameCounter<LinearAverageSampler>, step::ConstantStep> {
+        let counter = FrameCounter::new(20.0, LinearAverageSampler::new());
+        let runner = FrameRunner::new(GameClock::new(), counter);
+        DebugController::new(runner, step::ConstantStep::new(FloatDuration::seconds(0.25)))
+    }
+
+    #[test]
+    fn test_play_pause_step() {
+        let mut controller = controller();
+        let play_step = step::VariableStep::new();
+        let start_time = controller.runner().clock().start_wall_time();
+        let wall_time = |s| start_time + chrono::Duration::seconds(s);
+
+        assert_eq!(controller.state(), DebugState::Playing);
+        let time = controller.update_with_wall_time(&play_step, wall_time(1)).unwrap();
+        assert_eq!(time.elapsed_game_time(), FloatDuration::seconds(1.0));
+
+        controller.toggle_pause();
+        assert!(controller.is_paused());
+        assert!(controller.update_with_wall_time(&play_step, wall_time(2)).is_none());
+
+        // A step advances by the debug step, however much wall time passed.
+        controller.step();
+        let time = controller.update_with_wall_time(&play_step, wall_time(60)).unwrap();
+        assert_eq!(time.elapsed_game_time(), FloatDuration::seconds(0.25));
+        assert_eq!(time.frame_number(), 2);
+        assert!(controller.is_paused());
+
+        controller.step_frames(3);
+        for i in 0..3 {
+            assert_eq!(controller.state(), DebugState::SteppingFrames(3 - i));
+            assert!(controller.update(&play_step).is_some());
+        }
+        assert!(controller.is_paused());
+        assert_eq!(
+            controller.runner().clock().last_frame_time().total_game_time(),
+            FloatDuration::seconds(2.0)
+        );
+
+        controller.toggle_pause();
+        assert_eq!(controller.state(), DebugState::Playing);
+    }
+
+    #[test]
+    fn test_step_for_and_breakpoint() {
+        let mut controller = controller();
+        let play_step = step::VariableStep::new();
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        {
+            let changes = changes.clone();
+            controller.on_state_change(move |_, new| changes.borrow_mut().push(new));
+        }
+
+        controller.step_for(FloatDuration::seconds(1.0));
+        let mut frames = 0;
+        while controller.update(&play_step).is_some() {
+            frames += 1;
+        }
+        assert_eq!(frames, 4);
+
+        controller.run_until_frame(10);
+        while controller.update(&play_step).is_some() {}
+        assert_eq!(controller.runner().clock().current_frame_number(), 10);
+
+        controller.run_until_frame(5);
+        assert!(controller.is_paused());
+
+        assert_eq!(
+            *changes.borrow(),
+            vec![
+                DebugState::SteppingFor(FloatDuration::seconds(1.0)),
+                DebugState::SteppingFor(FloatDuration::seconds(0.75)),
+                DebugState::SteppingFor(FloatDuration::seconds(0.5)),
+                DebugState::SteppingFor(FloatDuration::seconds(0.25)),
+                DebugState::Paused,
+                DebugState::RunningUntil(10),
+                DebugState::Paused,
+            ]
+        );
+    }
+}
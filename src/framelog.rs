@@ -0,0 +1,376 @@
+//! Recording of frame times to CSV logs for offline analysis.
+//!
+//! [`FrameLog`](./struct.FrameLog.html) appends one CSV row per frame to any `io::Write`,
+//! and [`FrameLogReader`](./struct.FrameLogReader.html) parses those rows back into
+//! [`FrameRecord`](./struct.FrameRecord.html)s. Each record contains a
+//! [`GameTime`](../clock/struct.GameTime.html), so logged frames can be fed back through
+//! frame rate samplers.
+//!
+//! Durations are written in seconds and the frame start time is written in RFC 3339 format.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use game_time::{GameClock, FrameCounter, FrameCount, FloatDuration};
+//! use game_time::framelog::{FrameLog, FrameLogReader};
+//! use game_time::framerate::LinearAverageSampler;
+//! use game_time::step;
+//!
+//! let mut clock = GameClock::new();
+//! let mut counter = FrameCounter::new(60.0, LinearAverageSampler::new());
+//! let mut log = FrameLog::new(Vec::new());
+//!
+//! for _ in 0..10 {
+//!     let time = clock.tick(&step::ConstantStep::new(FloatDuration::milliseconds(16.0)));
+//!     counter.tick(&time);
+//!     log.write_frame(&time, &counter).unwrap();
+//! }
+//!
+//! let data = log.into_inner();
+//! let records: Vec<_> = FrameLogReader::new(&data[..]).collect::<Result<_, _>>().unwrap();
+//! assert_eq!(records.len(), 10);
+//! assert_eq!(records[9].time().frame_number(), 10);
+//! ```
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use chrono::{self, TimeZone};
+use float_duration::FloatDuration;
+
+use clock::GameTime;
+use framerate::FrameCount;
+
+/// The header row of a frame log.
+pub const HEADER: &str = "frame_number,frame_start_time,total_wall_time,elapsed_wall_time,\
+                          elapsed_game_time,total_game_time,clock_multiplier,average_frame_rate";
+
+const NUM_COLUMNS: usize = 8;
+
+/// A writer of CSV frame logs.
+///
+/// The header row is written before the first frame, unless the log was constructed with
+/// [`without_header`](#method.without_header) to append to an existing log.
+#[derive(Debug)]
+pub struct FrameLog<W: Write> {
+    writer: W,
+    header_written: bool,
+}
+
+/// A single frame read from a frame log.
+#[derive(Debug, Clone)]
+pub struct FrameRecord {
+    time: GameTime,
+    average_frame_rate: Option<f64>,
+}
+
+/// A reader of CSV frame logs.
+///
+/// `FrameLogReader` is an iterator over the records of a log. Malformed rows are returned
+/// as errors of kind `io::ErrorKind::InvalidData`.
+#[derive(Debug)]
+pub struct FrameLogReader<R: BufRead> {
+    lines: io::Lines<R>,
+    line_number: usize,
+}
+
+impl<W: Write> FrameLog<W> {
+    /// Construct a new `FrameLog` writing to `writer`.
+    pub fn new(writer: W) -> FrameLog<W> {
+        FrameLog {
+            writer,
+            header_written: false,
+        }
+    }
+    /// Construct a new `FrameLog` that does not write a header row.
+    pub fn without_header(writer: W) -> FrameLog<W> {
+        FrameLog {
+            writer,
+            header_written: true,
+        }
+    }
+
+    /// Append a frame, along with the average frame rate of `counter`.
+    pub fn write_frame<C, Tz>(&mut self, time: &GameTime<Tz>, counter: &C) -> io::Result<()>
+    where
+        C: FrameCount + ?Sized,
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        self.write_row(time, Some(counter.average_frame_rate()))
+    }
+    /// Append a frame without an average frame rate.
+    pub fn write_time<Tz>(&mut self, time: &GameTime<Tz>) -> io::Result<()>
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        self.write_row(time, None)
+    }
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+    /// Return a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+    /// Consume the `FrameLog`, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_row<Tz>(
+        &mut self,
+        time: &GameTime<Tz>,
+        average_frame_rate: Option<f64>,
+    ) -> io::Result<()>
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        if !self.header_written {
+            writeln!(self.writer, "{}", HEADER)?;
+            self.header_written = true;
+        }
+        let average = match average_frame_rate {
+            Some(rate) => rate.to_string(),
+            None => String::new(),
+        };
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{},{},{}",
+            time.frame_number(),
+            time.frame_start_time().to_rfc3339(),
+            time.total_wall_time().as_seconds(),
+            time.elapsed_wall_time().as_seconds(),
+            time.elapsed_game_time().as_seconds(),
+            time.total_game_time().as_seconds(),
+            time.clock_multiplier(),
+            average
+        )
+    }
+}
+
+impl FrameRecord {
+    /// The recorded time of the frame.
+    pub fn time(&self) -> &GameTime {
+        &self.time
+    }
+    /// Consume the record, returning the recorded time of the frame.
+    pub fn into_time(self) -> GameTime {
+        self.time
+    }
+    /// The average frame rate reported when the frame was logged, if any.
+    pub fn average_frame_rate(&self) -> Option<f64> {
+        self.average_frame_rate
+    }
+}
+
+impl<R: BufRead> FrameLogReader<R> {
+    /// Construct a new `FrameLogReader` reading from `reader`.
+    pub fn new(reader: R) -> FrameLogReader<R> {
+        FrameLogReader {
+            lines: reader.lines(),
+            line_number: 0,
+        }
+    }
+
+    fn parse_row(&self, line: &str) -> io::Result<FrameRecord> {
+        let columns: Vec<&str> = line.split(',').map(|col| col.trim()).collect();
+        if columns.len() != NUM_COLUMNS {
+            return Err(self.invalid_data(&format!(
+                "expected {} columns, found {}",
+                NUM_COLUMNS,
+                columns.len()
+            )));
+        }
+
+        let frame_start_time = chrono::DateTime::parse_from_rfc3339(columns[1])
+            .map_err(|err| self.invalid_data(&err.to_string()))?
+            .with_timezone(&chrono::Local);
+        let average_frame_rate = if columns[7].is_empty() {
+            None
+        } else {
+            Some(self.parse_finite(columns[7])?)
+        };
+
+        // Game time runs backwards with a negative clock multiplier, but wall time never does.
+        let time = GameTime::from_parts(
+            frame_start_time,
+            self.parse_wall_seconds(columns[2])?,
+            self.parse_seconds(columns[5])?,
+            self.parse_seconds(columns[4])?,
+            self.parse_wall_seconds(columns[3])?,
+            self.parse(columns[0])?,
+            self.parse_finite(columns[6])?,
+        );
+        Ok(FrameRecord {
+            time,
+            average_frame_rate,
+        })
+    }
+
+    fn parse<T>(&self, column: &str) -> io::Result<T>
+    where
+        T: FromStr,
+        T::Err: ToString,
+    {
+        column
+            .parse()
+            .map_err(|err: T::Err| self.invalid_data(&err.to_string()))
+    }
+
+    fn parse_finite(&self, column: &str) -> io::Result<f64> {
+        let value: f64 = self.parse(column)?;
+        if value.is_finite() {
+            Ok(value)
+        } else {
+            Err(self.invalid_data(&format!("expected a finite number, found {}", column)))
+        }
+    }
+
+    fn parse_seconds(&self, column: &str) -> io::Result<FloatDuration> {
+        self.parse_finite(column).map(FloatDuration::seconds)
+    }
+
+    fn parse_wall_seconds(&self, column: &str) -> io::Result<FloatDuration> {
+        let seconds = self.parse_finite(column)?;
+        if seconds < 0.0 {
+            return Err(self.invalid_data(&format!(
+                "expected a non-negative wall time, found {}",
+                column
+            )));
+        }
+        Ok(FloatDuration::seconds(seconds))
+    }
+
+    fn invalid_data(&self, msg: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("line {}: {}", self.line_number, msg),
+        )
+    }
+}
+
+impl<R: BufRead> Iterator for FrameLogReader<R> {
+    type Item = io::Result<FrameRecord>;
+
+    fn next(&mut self) -> Option<io::Result<FrameRecord>> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err)),
+            };
+            self.line_number += 1;
+
+            let line = line.trim();
+            if line.is_empty() || line == HEADER {
+                continue;
+            }
+            return Some(self.parse_row(line));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::GameClockBuilder;
+    use framerate::{FrameCounter, LinearAverageSampler};
+    use step;
+
+    #[test]
+    fn test_round_trip() {
+        let mut clock = GameClockBuilder::new().clock_multiplier(0.5).build();
+        let mut counter = FrameCounter::new(20.0, LinearAverageSampler::new());
+        let step = step::VariableStep::new();
+        let start_time = clock.start_wall_time();
+        let mut log = FrameLog::new(Vec::new());
+        let mut times = Vec::new();
+
+        for i in 0..5 {
+            let frame_time = start_time + chrono::Duration::microseconds(33_333 * (i + 1));
+            let time = clock.tick_with_wall_time(&step, frame_time);
+            counter.tick(&time);
+            if i == 4 {
+                log.write_time(&time).unwrap();
+            } else {
+                log.write_frame(&time, &counter).unwrap();
+            }
+            times.push(time);
+        }
+
+        let data = String::from_utf8(log.into_inner()).unwrap();
+        assert_eq!(data.lines().next(), Some(HEADER));
+        assert_eq!(data.lines().count(), 6);
+
+        let records: Vec<_> = FrameLogReader::new(data.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records.len(), 5);
+
+        for (record, time) in records.iter().zip(times.iter()) {
+            let read = record.time();
+            assert_eq!(read.frame_number(), time.frame_number());
+            assert_eq!(read.frame_start_time(), time.frame_start_time());
+            assert_eq!(read.total_wall_time(), time.total_wall_time());
+            assert_eq!(read.elapsed_wall_time(), time.elapsed_wall_time());
+            assert_eq!(read.elapsed_game_time(), time.elapsed_game_time());
+            assert_eq!(read.total_game_time(), time.total_game_time());
+            assert_eq!(read.clock_multiplier(), 0.5);
+        }
+        assert!(relative_eq!(
+            records[0].average_frame_rate().unwrap(),
+            30.0003,
+            epsilon = 1e-3
+        ));
+        assert_eq!(records[4].average_frame_rate(), None);
+    }
+
+    #[test]
+    fn test_append_and_errors() {
+        let mut clock = GameClockBuilder::new().build();
+        let mut log = FrameLog::without_header(Vec::new());
+        log.write_time(&clock.tick(&step::VariableStep::new())).unwrap();
+        let mut data = String::from_utf8(log.into_inner()).unwrap();
+        assert!(!data.starts_with("frame_number"));
+
+        data.push_str("\n2,not a date,0,0,0,0,1,\n3,4\n");
+        let mut reader = FrameLogReader::new(data.as_bytes());
+        assert!(reader.next().unwrap().is_ok());
+
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 3:"));
+
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "line 4: expected 8 columns, found 2");
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_invalid_durations() {
+        let mut clock = GameClockBuilder::new().build();
+        let mut log = FrameLog::without_header(Vec::new());
+        log.write_time(&clock.tick(&step::VariableStep::new())).unwrap();
+        let row = String::from_utf8(log.into_inner()).unwrap();
+        let columns: Vec<&str> = row.trim().split(',').collect();
+
+        let with_column = |index: usize, value: &str| {
+            let mut columns = columns.clone();
+            columns[index] = value;
+            columns.join(",")
+        };
+        for &(index, value) in &[(3, "NaN"), (2, "inf"), (3, "-0.01"), (5, "-inf"), (6, "NaN")] {
+            let data = with_column(index, value);
+            let err = FrameLogReader::new(data.as_bytes()).next().unwrap().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{}", data);
+        }
+
+        // Game time may run backwards.
+        let data = with_column(4, "-0.01");
+        let record = FrameLogReader::new(data.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(record.time().elapsed_game_time(), FloatDuration::milliseconds(-10.0));
+    }
+}
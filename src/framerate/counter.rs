@@ -2,13 +2,16 @@
 use std::fmt::Debug;
 
 use float_duration::FloatDuration;
-use clock::GameTime;
+use clock::FrameTiming;
 use super::FrameRateSampler;
 
 /// The default slow threshold for `FrameCounter`.
 pub const DEFAULT_SLOW_THRESHOLD: f64 = 0.95;
 
 /// A frame rate counter and tracker.
+///
+/// Methods taking a [`FrameTiming`](../../clock/trait.FrameTiming.html) accept a `GameTime`
+/// from a clock in any time zone.
 pub trait FrameCount: Debug {
     /// The target frame rate for the simulation.
     fn target_frame_rate(&self) -> f64;
@@ -18,13 +21,13 @@ pub trait FrameCount: Debug {
     ///
     /// The duration can be negative if the current frame has
     /// executed longer than the optimal time.
-    fn remaining_frame_time(&self, time: &GameTime) -> FloatDuration;
+    fn remaining_frame_time(&self, time: &dyn FrameTiming) -> FloatDuration;
     /// Update the counter for a new frame.
-    fn tick(&mut self, time: &GameTime);
+    fn tick(&mut self, time: &dyn FrameTiming);
     /// The average frame rate for the current frame.
     fn average_frame_rate(&self) -> f64;
     /// Return whether the simulation is running slowly.
-    fn is_running_slow(&self, time: &GameTime) -> bool;
+    fn is_running_slow(&self, time: &dyn FrameTiming) -> bool;
 }
 
 /// A basic frame rate counter.
@@ -81,27 +84,52 @@ impl<S: FrameRateSampler> FrameCount for FrameCounter<S> {
     fn target_time_per_frame(&self) -> FloatDuration {
         FloatDuration::seconds(1.0) / self.target_frame_rate
     }
-    fn remaining_frame_time(&self, time: &GameTime) -> FloatDuration {
+    fn remaining_frame_time(&self, time: &dyn FrameTiming) -> FloatDuration {
         self.target_time_per_frame() - time.elapsed_time_since_frame_start()
     }
-    fn tick(&mut self, time: &GameTime) {
+    fn tick(&mut self, time: &dyn FrameTiming) {
         self.sampler.tick(time);
     }
     fn average_frame_rate(&self) -> f64 {
         self.sampler.average_frame_rate()
     }
-    fn is_running_slow(&self, time: &GameTime) -> bool {
+    fn is_running_slow(&self, time: &dyn FrameTiming) -> bool {
         let ratio = self.target_time_per_frame().as_seconds() /
             time.elapsed_wall_time().as_seconds();
         ratio <= self.slow_threshold
     }
 }
 
+impl<C: FrameCount + ?Sized> FrameCount for Box<C> {
+    fn target_frame_rate(&self) -> f64 {
+        (**self).target_frame_rate()
+    }
+    fn target_time_per_frame(&self) -> FloatDuration {
+        (**self).target_time_per_frame()
+    }
+    fn remaining_frame_time(&self, time: &dyn FrameTiming) -> FloatDuration {
+        (**self).remaining_frame_time(time)
+    }
+    fn tick(&mut self, time: &dyn FrameTiming) {
+        (**self).tick(time)
+    }
+    fn average_frame_rate(&self) -> f64 {
+        (**self).average_frame_rate()
+    }
+    fn is_running_slow(&self, time: &dyn FrameTiming) -> bool {
+        (**self).is_running_slow(time)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use chrono;
+    use clock::GameClock;
     use framerate::sample;
+    use runner::FrameRunner;
+    use step;
 
     #[test]
     fn test_construction() {
@@ -112,4 +140,28 @@ mod tests {
         assert_eq!(counter.target_frame_rate(), 5.0);
         assert_eq!(counter.slow_threshold(), DEFAULT_SLOW_THRESHOLD);
     }
+
+    #[test]
+    fn test_trait_objects() {
+        let sampler: Box<dyn FrameRateSampler> =
+            Box::new(sample::LinearAverageSampler::with_max_samples(10));
+        let counter: Box<dyn FrameCount> = Box::new(FrameCounter::new(50.0, sampler));
+        let clock = GameClock::with_time_zone(chrono::Utc);
+        let start = clock.start_wall_time();
+        let mut runner = FrameRunner::new(clock, counter);
+
+        for i in 1..11 {
+            let frame_start = start + chrono::Duration::milliseconds(20 * i);
+            runner.tick_with_wall_time(&step::VariableStep::new(), frame_start);
+        }
+        let counter = runner.counter();
+        assert!(relative_eq!(counter.average_frame_rate(), 50.0, epsilon = 1e-6));
+        assert!(relative_eq!(
+            counter.target_time_per_frame().as_seconds(),
+            0.02,
+            epsilon = 1e-12
+        ));
+        let time = runner.clock().last_frame_time();
+        assert!(!counter.is_running_slow(time));
+    }
 }
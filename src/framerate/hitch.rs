@@ -0,0 +1,347 @@
+//! Provides detection of hitches, frames that take much longer than expected.
+use std::collections::VecDeque;
+use std::collections::vec_deque::Drain;
+use std::fmt;
+
+use float_duration::FloatDuration;
+use clock::FrameTiming;
+use super::FrameCount;
+use super::sample::{self, DEFAULT_NUM_SAMPLES};
+
+/// The default multiple of the expected frame time before a frame is a hitch.
+pub const DEFAULT_HITCH_MULTIPLIER: f64 = 2.0;
+/// The default number of hitch events kept until they are drained.
+pub const DEFAULT_MAX_QUEUED_EVENTS: usize = 64;
+
+type HitchCallback = Box<dyn FnMut(&HitchEvent)>;
+
+/// The expected frame time hitches are measured against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitchBaseline {
+    /// The median wall time of this many past frames.
+    RollingMedian(u32),
+    /// The target time per frame of the wrapped counter.
+    TargetFrameTime,
+}
+
+/// A record of a single hitch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitchEvent {
+    frame_number: u64,
+    duration: FloatDuration,
+    expected_duration: FloatDuration,
+    streak: u32,
+}
+
+/// A [`FrameCount`](../counter/trait.FrameCount.html) wrapper that detects hitches.
+///
+/// Each frame passed to `tick` is classified as a hitch if its elapsed wall time exceeds
+/// a multiple of the expected frame time given by a [`HitchBaseline`](./enum.HitchBaseline.html).
+/// Every hitch is passed to the registered callbacks and queued as a
+/// [`HitchEvent`](./struct.HitchEvent.html) until it is drained. Consecutive hitches are
+/// tracked as a streak.
+///
+/// All other `FrameCount` methods are forwarded to the wrapped counter.
+pub struct HitchDetector<C: FrameCount> {
+    counter: C,
+    baseline: HitchBaseline,
+    multiplier: f64,
+    past_data: VecDeque<f64>,
+    hitch_count: u64,
+    current_streak: u32,
+    longest_streak: u32,
+    events: VecDeque<HitchEvent>,
+    max_queued_events: usize,
+    callbacks: Vec<HitchCallback>,
+}
+
+impl HitchEvent {
+    /// The number of the frame that hitched.
+    pub fn frame_number(&self) -> u64 {
+        self.frame_number
+    }
+    /// The elapsed wall time of the frame.
+    pub fn duration(&self) -> FloatDuration {
+        self.duration
+    }
+    /// The frame time that was expected from the baseline.
+    pub fn expected_duration(&self) -> FloatDuration {
+        self.expected_duration
+    }
+    /// The frame time relative to the expected frame time.
+    ///
+    /// A severity of `3.0` means the frame took three times as long as expected.
+    pub fn severity(&self) -> f64 {
+        self.duration.as_seconds() / self.expected_duration.as_seconds()
+    }
+    /// The number of consecutive hitches up to and including this one.
+    pub fn streak(&self) -> u32 {
+        self.streak
+    }
+}
+
+impl<C: FrameCount> HitchDetector<C> {
+    /// Construct a new `HitchDetector` wrapping `counter`.
+    ///
+    /// Hitches are measured against the median of a default number of past frames.
+    pub fn new(counter: C) -> HitchDetector<C> {
+        HitchDetector::with_baseline(counter, HitchBaseline::RollingMedian(DEFAULT_NUM_SAMPLES))
+    }
+    /// Construct a new `HitchDetector` wrapping `counter` with a specified baseline.
+    pub fn with_baseline(counter: C, baseline: HitchBaseline) -> HitchDetector<C> {
+        HitchDetector {
+            counter,
+            baseline,
+            multiplier: DEFAULT_HITCH_MULTIPLIER,
+            past_data: VecDeque::new(),
+            hitch_count: 0,
+            current_streak: 0,
+            longest_streak: 0,
+            events: VecDeque::new(),
+            max_queued_events: DEFAULT_MAX_QUEUED_EVENTS,
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Return a reference to the wrapped counter.
+    pub fn counter(&self) -> &C {
+        &self.counter
+    }
+    /// Return a mutable reference to the wrapped counter.
+    pub fn counter_mut(&mut self) -> &mut C {
+        &mut self.counter
+    }
+    /// Consume the `HitchDetector`, returning the wrapped counter.
+    pub fn into_inner(self) -> C {
+        self.counter
+    }
+
+    /// Return the baseline hitches are measured against.
+    pub fn baseline(&self) -> HitchBaseline {
+        self.baseline
+    }
+    /// Set the baseline hitches are measured against.
+    pub fn set_baseline(&mut self, baseline: HitchBaseline) -> &mut HitchDetector<C> {
+        self.baseline = baseline;
+        self
+    }
+    /// Return the multiple of the expected frame time before a frame is a hitch.
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+    /// Set the multiple of the expected frame time before a frame is a hitch.
+    pub fn set_multiplier(&mut self, val: f64) -> &mut HitchDetector<C> {
+        self.multiplier = val;
+        self
+    }
+    /// Return the maximum number of events queued until they are drained.
+    pub fn max_queued_events(&self) -> usize {
+        self.max_queued_events
+    }
+    /// Set the maximum number of events queued until they are drained.
+    ///
+    /// When the queue is full, the oldest event is discarded. Setting this to `0`
+    /// disables the queue, which is useful if only callbacks are used.
+    pub fn set_max_queued_events(&mut self, val: usize) -> &mut HitchDetector<C> {
+        self.max_queued_events = val;
+        while self.events.len() > val {
+            self.events.pop_front();
+        }
+        self
+    }
+
+    /// Register a callback to be invoked for every hitch.
+    pub fn on_hitch<F>(&mut self, callback: F) -> &mut HitchDetector<C>
+    where
+        F: FnMut(&HitchEvent) + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+    /// Remove and return all queued hitch events, oldest first.
+    pub fn drain_events(&mut self) -> Drain<'_, HitchEvent> {
+        self.events.drain(..)
+    }
+
+    /// Return the total number of hitches detected.
+    pub fn hitch_count(&self) -> u64 {
+        self.hitch_count
+    }
+    /// Return the number of consecutive hitches up to the last frame.
+    ///
+    /// This is zero if the last frame was not a hitch.
+    pub fn current_streak(&self) -> u32 {
+        self.current_streak
+    }
+    /// Return the longest run of consecutive hitches seen.
+    pub fn longest_streak(&self) -> u32 {
+        self.longest_streak
+    }
+    /// Return the frame time expected of the next frame.
+    ///
+    /// Returns `None` if a rolling median is used and no frames have been seen yet.
+    pub fn expected_frame_time(&self) -> Option<FloatDuration> {
+        match self.baseline {
+            HitchBaseline::RollingMedian(_) => {
+                if self.past_data.is_empty() {
+                    None
+                } else {
+                    let median = sample::quantile(&sample::sorted_samples(&self.past_data), 0.5);
+                    Some(FloatDuration::seconds(median))
+                }
+            }
+            HitchBaseline::TargetFrameTime => Some(self.counter.target_time_per_frame()),
+        }
+    }
+    /// Return true if a frame with the elapsed wall time of `time` would be a hitch.
+    pub fn is_hitch(&self, time: &dyn FrameTiming) -> bool {
+        match self.expected_frame_time() {
+            Some(expected) => time.elapsed_wall_time() > expected * self.multiplier,
+            None => false,
+        }
+    }
+
+    fn record_hitch(&mut self, time: &dyn FrameTiming, expected_duration: FloatDuration) {
+        self.hitch_count += 1;
+        self.current_streak += 1;
+        self.longest_streak = self.longest_streak.max(self.current_streak);
+
+        let event = HitchEvent {
+            frame_number: time.frame_number(),
+            duration: time.elapsed_wall_time(),
+            expected_duration,
+            streak: self.current_streak,
+        };
+        for callback in &mut self.callbacks {
+            callback(&event);
+        }
+        if self.max_queued_events > 0 {
+            if self.events.len() == self.max_queued_events {
+                self.events.pop_front();
+            }
+            self.events.push_back(event);
+        }
+    }
+}
+
+impl<C: FrameCount> FrameCount for HitchDetector<C> {
+    fn target_frame_rate(&self) -> f64 {
+        self.counter.target_frame_rate()
+    }
+    fn target_time_per_frame(&self) -> FloatDuration {
+        self.counter.target_time_per_frame()
+    }
+    fn remaining_frame_time(&self, time: &dyn FrameTiming) -> FloatDuration {
+        self.counter.remaining_frame_time(time)
+    }
+    fn tick(&mut self, time: &dyn FrameTiming) {
+        match self.expected_frame_time() {
+            Some(expected) if time.elapsed_wall_time() > expected * self.multiplier => {
+                self.record_hitch(time, expected)
+            }
+            _ => self.current_streak = 0,
+        }
+
+        if let HitchBaseline::RollingMedian(max_samples) = self.baseline {
+            while self.past_data.len() >= max_samples as usize && !self.past_data.is_empty() {
+                self.past_data.pop_front();
+            }
+            self.past_data.push_back(time.elapsed_wall_time().as_seconds());
+        }
+        self.counter.tick(time);
+    }
+    fn average_frame_rate(&self) -> f64 {
+        self.counter.average_frame_rate()
+    }
+    fn is_running_slow(&self, time: &dyn FrameTiming) -> bool {
+        self.counter.is_running_slow(time)
+    }
+}
+
+impl<C: FrameCount> fmt::Debug for HitchDetector<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HitchDetector")
+            .field("counter", &self.counter)
+            .field("baseline", &self.baseline)
+            .field("multiplier", &self.multiplier)
+            .field("hitch_count", &self.hitch_count)
+            .field("current_streak", &self.current_streak)
+            .field("longest_streak", &self.longest_streak)
+            .field("events", &self.events)
+            .field("max_queued_events", &self.max_queued_events)
+            .field("callbacks", &self.callbacks.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use chrono;
+    use clock::GameClock;
+    use framerate::{FrameCounter, LinearAverageSampler};
+    use step;
+
+    fn run_detector<C: FrameCount>(detector: &mut HitchDetector<C>, frame_ms: &[i64]) {
+        let mut clock = GameClock::default();
+        let step = step::VariableStep::new();
+        let mut frame_time = clock.start_wall_time();
+
+        for &ms in frame_ms {
+            frame_time += chrono::Duration::milliseconds(ms);
+            let time = clock.tick_with_wall_time(&step, frame_time);
+            detector.tick(&time);
+        }
+    }
+
+    #[test]
+    fn test_target_frame_time_hitches() {
+        let counter = FrameCounter::new(20.0, LinearAverageSampler::new());
+        let mut detector = HitchDetector::with_baseline(counter, HitchBaseline::TargetFrameTime);
+        let frames = Rc::new(RefCell::new(Vec::new()));
+        {
+            let frames = frames.clone();
+            detector.on_hitch(move |event| frames.borrow_mut().push(event.frame_number()));
+        }
+
+        run_detector(&mut detector, &[50, 50, 150, 120, 50, 200]);
+
+        assert_eq!(detector.hitch_count(), 3);
+        assert_eq!(detector.longest_streak(), 2);
+        assert_eq!(detector.current_streak(), 1);
+        assert_eq!(*frames.borrow(), vec![3, 4, 6]);
+
+        let events: Vec<_> = detector.drain_events().collect();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].duration(), FloatDuration::milliseconds(150.0));
+        assert_eq!(events[0].expected_duration(), FloatDuration::milliseconds(50.0));
+        assert!(relative_eq!(events[0].severity(), 3.0, epsilon = 1e-8));
+        assert_eq!(events[1].streak(), 2);
+        assert_eq!(detector.drain_events().count(), 0);
+
+        assert_eq!(detector.target_frame_rate(), 20.0);
+        assert_eq!(detector.counter().target_frame_rate(), 20.0);
+    }
+
+    #[test]
+    fn test_rolling_median_hitches() {
+        let counter = FrameCounter::new(20.0, LinearAverageSampler::new());
+        let mut detector = HitchDetector::new(counter);
+        detector.set_multiplier(3.0).set_max_queued_events(1);
+        assert_eq!(detector.expected_frame_time(), None);
+
+        run_detector(&mut detector, &[10, 11, 9, 10, 40, 25, 10, 50]);
+
+        assert_eq!(detector.hitch_count(), 2);
+        assert_eq!(detector.longest_streak(), 1);
+        let expected = detector.expected_frame_time().unwrap();
+        assert!(relative_eq!(expected.as_seconds(), 0.0105, epsilon = 1e-8));
+
+        let events: Vec<_> = detector.drain_events().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].frame_number(), 8);
+    }
+}
@@ -1,6 +1,11 @@
 //! Utilities for frame rate computation and management.
 pub mod counter;
+pub mod hitch;
 pub mod sample;
 
 pub use self::counter::{FrameCounter, FrameCount};
-pub use self::sample::{FrameRateSampler, RunningAverageSampler, LinearAverageSampler};
+pub use self::hitch::{HitchDetector, HitchBaseline, HitchEvent};
+pub use self::sample::{FrameRateSampler, RunningAverageSampler, LinearAverageSampler,
+                       ExponentialSampler, MedianSampler, TrimmedMeanSampler,
+                       SlidingWindowSampler, TumblingWindowSampler,
+                       AveragingMode, OutlierThreshold};
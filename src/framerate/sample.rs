@@ -3,24 +3,57 @@ use std::fmt::Debug;
 
 use std::collections::VecDeque;
 
-use clock::GameTime;
+use float_duration::FloatDuration;
+
+use clock::FrameTiming;
 
 /// The default number of samples for frame rate samplers.
 pub const DEFAULT_NUM_SAMPLES: u32 = 64;
+/// The default half-life, in seconds, for `ExponentialSampler`.
+pub const DEFAULT_HALF_LIFE: f64 = 0.5;
+/// The default number of median absolute deviations before a frame is an outlier.
+pub const DEFAULT_MAD_THRESHOLD: f64 = 3.0;
+/// The smallest median absolute deviation used by `OutlierThreshold::Mad`, as a fraction
+/// of the median frame time.
+pub const MIN_RELATIVE_MAD: f64 = 0.02;
+/// The default window length, in seconds, for time-windowed samplers.
+pub const DEFAULT_WINDOW: f64 = 1.0;
 
 /// Frame rate computation.
 ///
 /// `FrameRateSampler` provides methods to take the time at each frame and compute
-/// a frame rate metric through some method.
+/// a frame rate metric through some method. Frames from a clock in any time zone
+/// are accepted through [`FrameTiming`](../../clock/trait.FrameTiming.html).
 pub trait FrameRateSampler: Debug {
     /// Update the frame rate with a new frame.
-    fn tick(&mut self, time: &GameTime);
+    fn tick(&mut self, time: &dyn FrameTiming);
     /// Return the current frame rate measure.
     fn average_frame_rate(&self) -> f64;
     /// Return true if the number of samples fills the cache.
     fn is_saturated(&self) -> bool;
     /// Return the number of samples to average over.
     fn max_samples(&self) -> u32;
+    /// Return the length of wall time to average over.
+    ///
+    /// This is `None` for samplers that average over a number of frames rather than
+    /// a span of time.
+    fn window_duration(&self) -> Option<FloatDuration> {
+        None
+    }
+}
+
+/// The quantity averaged by a frame rate sampler.
+///
+/// Averaging frame rates weighs every frame equally, so a few long frames barely move
+/// the result. Averaging frame times weighs each frame by its duration, and the result
+/// is always the number of frames divided by the time they took.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AveragingMode {
+    /// Average the instantaneous frame rate of each frame.
+    #[default]
+    FrameRate,
+    /// Average the duration of each frame, and report `frames / total_time`.
+    FrameTime,
 }
 
 /// A frame rate sampler that computes a moving average from past frames without caching data.
@@ -33,6 +66,8 @@ pub struct RunningAverageSampler {
     max_samples: u32,
     current_samples: u32,
     current_average: f64,
+    current_average_time: f64,
+    mode: AveragingMode,
 }
 
 /// A frame rate sampler that computes the average frame rate of a number of past frames.
@@ -40,6 +75,118 @@ pub struct RunningAverageSampler {
 pub struct LinearAverageSampler {
     past_data: VecDeque<f64>,
     max_samples: u32,
+    mode: AveragingMode,
+}
+
+/// A frame rate sampler that computes an exponential moving average with a set half-life.
+///
+/// Unlike `RunningAverageSampler`, the weight given to each frame depends on the wall time
+/// it took rather than on a number of frames. After one half-life of wall time has passed,
+/// older frames account for half of the average regardless of the frame rate, so the
+/// reading responds the same way at 30 fps and at 240 fps.
+#[derive(Debug, Clone)]
+pub struct ExponentialSampler {
+    half_life: FloatDuration,
+    sampled_time: FloatDuration,
+    current_samples: u64,
+    current_average: f64,
+    current_average_time: f64,
+    mode: AveragingMode,
+}
+
+/// The rule used to reject outlying frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutlierThreshold {
+    /// Reject frames further than this many median absolute deviations from the median
+    /// frame time.
+    ///
+    /// The deviation is at least `MIN_RELATIVE_MAD` of the median, so that frames with
+    /// nearly constant times do not make every slightly longer frame an outlier.
+    Mad(f64),
+    /// Reject this fraction of frames from each end of the sorted frame times.
+    ///
+    /// The value must be in the range `[0.0, 0.5)`.
+    Percentile(f64),
+}
+
+impl Default for OutlierThreshold {
+    fn default() -> OutlierThreshold {
+        OutlierThreshold::Mad(DEFAULT_MAD_THRESHOLD)
+    }
+}
+
+/// A frame rate sampler that computes the median frame rate of a number of past frames.
+///
+/// The median is not moved by a single hitch, keeping the reported frame rate stable.
+/// Frames that are longer than the threshold allows are still counted as stutters, see
+/// [`stutter_count`](#method.stutter_count).
+#[derive(Debug, Clone)]
+pub struct MedianSampler {
+    past_data: VecDeque<f64>,
+    max_samples: u32,
+    threshold: OutlierThreshold,
+    stutter_count: u64,
+}
+
+/// A frame rate sampler that averages a number of past frames, excluding outliers.
+///
+/// Frames outside of the threshold are left out of the average. The long ones are
+/// counted as stutters, see [`stutter_count`](#method.stutter_count).
+#[derive(Debug, Clone)]
+pub struct TrimmedMeanSampler {
+    past_data: VecDeque<f64>,
+    max_samples: u32,
+    threshold: OutlierThreshold,
+    stutter_count: u64,
+    mode: AveragingMode,
+}
+
+/// A frame rate sampler that averages the frames within a span of past wall time.
+///
+/// Unlike `LinearAverageSampler`, the span of time being averaged does not depend on the
+/// frame rate. Enough frames are kept to cover the window, so the number of cached frames
+/// varies with the frame rate.
+#[derive(Debug, Clone)]
+pub struct SlidingWindowSampler {
+    past_data: VecDeque<f64>,
+    covered_nanos: i64,
+    window: FloatDuration,
+    mode: AveragingMode,
+}
+
+/// A frame rate sampler that updates its reading once per interval of wall time.
+///
+/// This is the classic "frames in the last second" counter. The frames in each interval are
+/// averaged together once the interval is over, and the result is reported until the next
+/// interval is over. Until the first interval is complete, the frames seen so far are
+/// reported instead.
+#[derive(Debug, Clone)]
+pub struct TumblingWindowSampler {
+    bucket_samples: u32,
+    bucket_time: f64,
+    bucket_rate_sum: f64,
+    last_samples: u32,
+    last_average: Option<f64>,
+    window: FloatDuration,
+    mode: AveragingMode,
+}
+
+impl<S: FrameRateSampler + ?Sized> FrameRateSampler for Box<S> {
+    fn tick(&mut self, time: &dyn FrameTiming) {
+        (**self).tick(time)
+    }
+    fn average_frame_rate(&self) -> f64 {
+        (**self).average_frame_rate()
+    }
+    fn is_saturated(&self) -> bool {
+        (**self).is_saturated()
+    }
+    fn max_samples(&self) -> u32 {
+        (**self).max_samples()
+    }
+    fn window_duration(&self) -> Option<FloatDuration> {
+        (**self).window_duration()
+    }
 }
 
 impl RunningAverageSampler {
@@ -54,25 +201,47 @@ impl RunningAverageSampler {
             max_samples: max_samples,
             current_samples: 0,
             current_average: 0.0,
+            current_average_time: 0.0,
+            mode: AveragingMode::default(),
         }
     }
+
+    /// Return the quantity being averaged.
+    pub fn averaging_mode(&self) -> AveragingMode {
+        self.mode
+    }
+    /// Set the quantity being averaged.
+    ///
+    /// Both averages are tracked at all times, so the mode can be changed without
+    /// discarding past samples.
+    pub fn set_averaging_mode(&mut self, mode: AveragingMode) -> &mut RunningAverageSampler {
+        self.mode = mode;
+        self
+    }
 }
 
 impl FrameRateSampler for RunningAverageSampler {
-    fn tick(&mut self, time: &GameTime) {
+    fn tick(&mut self, time: &dyn FrameTiming) {
         if !self.is_saturated() {
             self.current_samples += 1;
         }
         let num_samples = self.current_samples;
 
-        let effective_fps = 1.0 / time.elapsed_wall_time().as_seconds();
+        let frame_time = time.elapsed_wall_time().as_seconds();
+        let effective_fps = 1.0 / frame_time;
         let new_average = ((self.current_average * (num_samples - 1) as f64) + effective_fps) /
             (num_samples as f64);
+        let new_average_time = ((self.current_average_time * (num_samples - 1) as f64) +
+                                    frame_time) / (num_samples as f64);
 
         self.current_average = new_average;
+        self.current_average_time = new_average_time;
     }
     fn average_frame_rate(&self) -> f64 {
-        self.current_average
+        match self.mode {
+            AveragingMode::FrameRate => self.current_average,
+            AveragingMode::FrameTime => 1.0 / self.current_average_time,
+        }
     }
     fn is_saturated(&self) -> bool {
         self.current_samples == self.max_samples
@@ -98,23 +267,36 @@ impl LinearAverageSampler {
         LinearAverageSampler {
             past_data: VecDeque::with_capacity(max_samples as usize),
             max_samples,
+            mode: AveragingMode::default(),
         }
     }
+
+    /// Return the quantity being averaged.
+    pub fn averaging_mode(&self) -> AveragingMode {
+        self.mode
+    }
+    /// Set the quantity being averaged.
+    ///
+    /// Past frame times are cached regardless of the mode, so the mode can be changed
+    /// without discarding past samples.
+    pub fn set_averaging_mode(&mut self, mode: AveragingMode) -> &mut LinearAverageSampler {
+        self.mode = mode;
+        self
+    }
 }
 
 impl FrameRateSampler for LinearAverageSampler {
-    fn tick(&mut self, time: &GameTime) {
-        let effective_fps = 1.0 / time.elapsed_wall_time().as_seconds();
+    fn tick(&mut self, time: &dyn FrameTiming) {
+        let frame_time = time.elapsed_wall_time().as_seconds();
 
         if self.is_saturated() {
             self.past_data.pop_front();
         }
-        self.past_data.push_back(effective_fps);
+        self.past_data.push_back(frame_time);
     }
 
     fn average_frame_rate(&self) -> f64 {
-        let sum: f64 = self.past_data.iter().sum();
-        sum / (self.past_data.len() as f64)
+        self.mode.average(&self.past_data)
     }
     fn is_saturated(&self) -> bool {
         self.past_data.len() == (self.max_samples as usize)
@@ -130,6 +312,502 @@ impl Default for LinearAverageSampler {
     }
 }
 
+impl ExponentialSampler {
+    /// Construct a new `ExponentialSampler` with a default half-life.
+    pub fn new() -> ExponentialSampler {
+        ExponentialSampler::with_half_life(FloatDuration::seconds(DEFAULT_HALF_LIFE))
+    }
+    /// Construct a new `ExponentialSampler` with a specified half-life.
+    ///
+    /// `half_life` is the amount of wall time after which a frame's contribution
+    /// to the average is halved.
+    pub fn with_half_life(half_life: FloatDuration) -> ExponentialSampler {
+        ExponentialSampler {
+            half_life,
+            sampled_time: FloatDuration::zero(),
+            current_samples: 0,
+            current_average: 0.0,
+            current_average_time: 0.0,
+            mode: AveragingMode::default(),
+        }
+    }
+
+    /// Return the half-life of the average.
+    pub fn half_life(&self) -> FloatDuration {
+        self.half_life
+    }
+    /// Set the half-life of the average.
+    pub fn set_half_life(&mut self, half_life: FloatDuration) -> &mut ExponentialSampler {
+        self.half_life = half_life;
+        self
+    }
+    /// Return the quantity being averaged.
+    pub fn averaging_mode(&self) -> AveragingMode {
+        self.mode
+    }
+    /// Set the quantity being averaged.
+    ///
+    /// Both averages are tracked at all times, so the mode can be changed without
+    /// discarding past samples.
+    pub fn set_averaging_mode(&mut self, mode: AveragingMode) -> &mut ExponentialSampler {
+        self.mode = mode;
+        self
+    }
+    /// Return the smoothing factor applied to a frame lasting `frame_time`.
+    ///
+    /// This is the weight given to the new frame, with the existing average
+    /// receiving the remaining weight.
+    pub fn smoothing_factor(&self, frame_time: &FloatDuration) -> f64 {
+        1.0 - (-frame_time.as_seconds() / self.half_life.as_seconds()).exp2()
+    }
+}
+
+impl FrameRateSampler for ExponentialSampler {
+    fn tick(&mut self, time: &dyn FrameTiming) {
+        let elapsed_time = time.elapsed_wall_time();
+        let frame_time = elapsed_time.as_seconds();
+        let effective_fps = 1.0 / frame_time;
+
+        if self.current_samples == 0 {
+            self.current_average = effective_fps;
+            self.current_average_time = frame_time;
+        } else {
+            let alpha = self.smoothing_factor(&elapsed_time);
+            self.current_average += alpha * (effective_fps - self.current_average);
+            self.current_average_time += alpha * (frame_time - self.current_average_time);
+        }
+
+        self.current_samples += 1;
+        if !self.is_saturated() {
+            self.sampled_time += elapsed_time;
+        }
+    }
+    fn average_frame_rate(&self) -> f64 {
+        match self.mode {
+            AveragingMode::FrameRate => self.current_average,
+            AveragingMode::FrameTime => 1.0 / self.current_average_time,
+        }
+    }
+    /// Return true once at least one half-life of wall time has been sampled.
+    fn is_saturated(&self) -> bool {
+        self.sampled_time >= self.half_life
+    }
+    /// Return the number of frames that fit in one half-life at the current frame rate.
+    ///
+    /// `ExponentialSampler` does not have a fixed number of samples, so this value is
+    /// only an estimate of the span of the average.
+    fn max_samples(&self) -> u32 {
+        if self.current_samples == 0 {
+            return 0;
+        }
+        (self.half_life.as_seconds() / self.current_average_time).round() as u32
+    }
+}
+
+impl Default for ExponentialSampler {
+    fn default() -> ExponentialSampler {
+        ExponentialSampler::new()
+    }
+}
+
+impl AveragingMode {
+    /// Average a set of frame times, given in seconds, according to the mode.
+    fn average<'a, I>(&self, frame_times: I) -> f64
+    where
+        I: IntoIterator<Item = &'a f64>,
+    {
+        let (num_samples, sum) = match *self {
+            AveragingMode::FrameRate => {
+                frame_times.into_iter().fold((0.0, 0.0), |(n, sum), dt| (n + 1.0, sum + 1.0 / dt))
+            }
+            AveragingMode::FrameTime => {
+                frame_times.into_iter().fold((0.0, 0.0), |(n, sum), dt| (n + 1.0, sum + dt))
+            }
+        };
+        match *self {
+            AveragingMode::FrameRate => sum / num_samples,
+            AveragingMode::FrameTime => num_samples / sum,
+        }
+    }
+}
+
+impl OutlierThreshold {
+    /// Panic if the percentile of a `Percentile` threshold is outside `[0.0, 0.5)`.
+    fn assert_valid(&self) {
+        if let OutlierThreshold::Percentile(p) = *self {
+            assert!(
+                (0.0..0.5).contains(&p),
+                "outlier percentile must be in [0.0, 0.5), got {}",
+                p
+            );
+        }
+    }
+    /// Return the range of accepted values for a sorted, non-empty set of samples.
+    fn bounds(&self, sorted: &[f64]) -> (f64, f64) {
+        match *self {
+            OutlierThreshold::Mad(k) => {
+                let median = quantile(sorted, 0.5);
+                let mut deviations: Vec<f64> = sorted.iter().map(|x| (x - median).abs()).collect();
+                sort_samples(&mut deviations);
+                let mad = quantile(&deviations, 0.5).max(median * MIN_RELATIVE_MAD);
+                (median - k * mad, median + k * mad)
+            }
+            OutlierThreshold::Percentile(p) => (quantile(sorted, p), quantile(sorted, 1.0 - p)),
+        }
+    }
+    /// Return true if `frame_time` is a stutter relative to `past_data`.
+    fn is_stutter(&self, past_data: &VecDeque<f64>, frame_time: f64) -> bool {
+        if past_data.is_empty() {
+            return false;
+        }
+        let (_, upper) = self.bounds(&sorted_samples(past_data));
+        frame_time > upper
+    }
+}
+
+fn sort_samples(samples: &mut [f64]) {
+    samples.sort_by(f64::total_cmp);
+}
+
+pub(crate) fn sorted_samples(past_data: &VecDeque<f64>) -> Vec<f64> {
+    let mut samples: Vec<f64> = past_data.iter().cloned().collect();
+    sort_samples(&mut samples);
+    samples
+}
+
+/// Convert a time in seconds to whole nanoseconds.
+fn nanos(seconds: f64) -> i64 {
+    (seconds * 1e9).round() as i64
+}
+
+/// Compute the `q`-quantile of sorted samples, interpolating between adjacent samples.
+pub(crate) fn quantile(sorted: &[f64], q: f64) -> f64 {
+    debug_assert!((0.0..=1.0).contains(&q), "quantile {} is outside [0.0, 1.0]", q);
+    let pos = q * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let upper = pos.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f64)
+}
+
+impl MedianSampler {
+    /// Construct a new `MedianSampler` with a default sample size.
+    pub fn new() -> MedianSampler {
+        MedianSampler::with_max_samples(DEFAULT_NUM_SAMPLES)
+    }
+    /// Construct a new `MedianSampler` with a specified sample size.
+    pub fn with_max_samples(max_samples: u32) -> MedianSampler {
+        MedianSampler {
+            past_data: VecDeque::with_capacity(max_samples as usize),
+            max_samples,
+            threshold: OutlierThreshold::default(),
+            stutter_count: 0,
+        }
+    }
+
+    /// Return the threshold used to detect stutters.
+    pub fn threshold(&self) -> OutlierThreshold {
+        self.threshold
+    }
+    /// Set the threshold used to detect stutters.
+    ///
+    /// Panics if a `Percentile` threshold is outside `[0.0, 0.5)`.
+    pub fn set_threshold(&mut self, threshold: OutlierThreshold) -> &mut MedianSampler {
+        threshold.assert_valid();
+        self.threshold = threshold;
+        self
+    }
+    /// Return the number of frames that were longer than the threshold allows.
+    ///
+    /// A frame is checked against the frames preceding it when it is sampled.
+    pub fn stutter_count(&self) -> u64 {
+        self.stutter_count
+    }
+    /// Reset the stutter count to zero.
+    pub fn reset_stutter_count(&mut self) -> &mut MedianSampler {
+        self.stutter_count = 0;
+        self
+    }
+}
+
+impl FrameRateSampler for MedianSampler {
+    fn tick(&mut self, time: &dyn FrameTiming) {
+        let frame_time = time.elapsed_wall_time().as_seconds();
+
+        if self.threshold.is_stutter(&self.past_data, frame_time) {
+            self.stutter_count += 1;
+        }
+        if self.is_saturated() {
+            self.past_data.pop_front();
+        }
+        self.past_data.push_back(frame_time);
+    }
+    fn average_frame_rate(&self) -> f64 {
+        if self.past_data.is_empty() {
+            return 0.0;
+        }
+        1.0 / quantile(&sorted_samples(&self.past_data), 0.5)
+    }
+    fn is_saturated(&self) -> bool {
+        self.past_data.len() == (self.max_samples as usize)
+    }
+    fn max_samples(&self) -> u32 {
+        self.max_samples
+    }
+}
+
+impl Default for MedianSampler {
+    fn default() -> MedianSampler {
+        MedianSampler::new()
+    }
+}
+
+impl TrimmedMeanSampler {
+    /// Construct a new `TrimmedMeanSampler` with a default sample size.
+    pub fn new() -> TrimmedMeanSampler {
+        TrimmedMeanSampler::with_max_samples(DEFAULT_NUM_SAMPLES)
+    }
+    /// Construct a new `TrimmedMeanSampler` with a specified sample size.
+    pub fn with_max_samples(max_samples: u32) -> TrimmedMeanSampler {
+        TrimmedMeanSampler {
+            past_data: VecDeque::with_capacity(max_samples as usize),
+            max_samples,
+            threshold: OutlierThreshold::default(),
+            stutter_count: 0,
+            mode: AveragingMode::default(),
+        }
+    }
+
+    /// Return the threshold used to reject outliers.
+    pub fn threshold(&self) -> OutlierThreshold {
+        self.threshold
+    }
+    /// Set the threshold used to reject outliers.
+    ///
+    /// Panics if a `Percentile` threshold is outside `[0.0, 0.5)`.
+    pub fn set_threshold(&mut self, threshold: OutlierThreshold) -> &mut TrimmedMeanSampler {
+        threshold.assert_valid();
+        self.threshold = threshold;
+        self
+    }
+    /// Return the quantity being averaged.
+    pub fn averaging_mode(&self) -> AveragingMode {
+        self.mode
+    }
+    /// Set the quantity being averaged.
+    pub fn set_averaging_mode(&mut self, mode: AveragingMode) -> &mut TrimmedMeanSampler {
+        self.mode = mode;
+        self
+    }
+    /// Return the number of frames that were longer than the threshold allows.
+    ///
+    /// A frame is checked against the frames preceding it when it is sampled.
+    pub fn stutter_count(&self) -> u64 {
+        self.stutter_count
+    }
+    /// Reset the stutter count to zero.
+    pub fn reset_stutter_count(&mut self) -> &mut TrimmedMeanSampler {
+        self.stutter_count = 0;
+        self
+    }
+    /// Return the number of cached frames currently excluded from the average.
+    pub fn rejected_samples(&self) -> usize {
+        self.past_data.len() - self.accepted_samples().len()
+    }
+
+    fn accepted_samples(&self) -> Vec<f64> {
+        if self.past_data.is_empty() {
+            return Vec::new();
+        }
+        let sorted = sorted_samples(&self.past_data);
+        let (lower, upper) = self.threshold.bounds(&sorted);
+        sorted
+            .into_iter()
+            .filter(|&x| x >= lower && x <= upper)
+            .collect()
+    }
+}
+
+impl FrameRateSampler for TrimmedMeanSampler {
+    fn tick(&mut self, time: &dyn FrameTiming) {
+        let frame_time = time.elapsed_wall_time().as_seconds();
+
+        if self.threshold.is_stutter(&self.past_data, frame_time) {
+            self.stutter_count += 1;
+        }
+        if self.is_saturated() {
+            self.past_data.pop_front();
+        }
+        self.past_data.push_back(frame_time);
+    }
+    fn average_frame_rate(&self) -> f64 {
+        self.mode.average(&self.accepted_samples())
+    }
+    fn is_saturated(&self) -> bool {
+        self.past_data.len() == (self.max_samples as usize)
+    }
+    fn max_samples(&self) -> u32 {
+        self.max_samples
+    }
+}
+
+impl Default for TrimmedMeanSampler {
+    fn default() -> TrimmedMeanSampler {
+        TrimmedMeanSampler::new()
+    }
+}
+
+impl SlidingWindowSampler {
+    /// Construct a new `SlidingWindowSampler` with a default window.
+    pub fn new() -> SlidingWindowSampler {
+        SlidingWindowSampler::with_window(FloatDuration::seconds(DEFAULT_WINDOW))
+    }
+    /// Construct a new `SlidingWindowSampler` averaging over `window` of wall time.
+    pub fn with_window(window: FloatDuration) -> SlidingWindowSampler {
+        SlidingWindowSampler {
+            past_data: VecDeque::new(),
+            covered_nanos: 0,
+            window,
+            mode: AveragingMode::default(),
+        }
+    }
+
+    /// Return the quantity being averaged.
+    pub fn averaging_mode(&self) -> AveragingMode {
+        self.mode
+    }
+    /// Set the quantity being averaged.
+    pub fn set_averaging_mode(&mut self, mode: AveragingMode) -> &mut SlidingWindowSampler {
+        self.mode = mode;
+        self
+    }
+    /// Return the number of frames currently cached.
+    pub fn num_samples(&self) -> usize {
+        self.past_data.len()
+    }
+}
+
+impl FrameRateSampler for SlidingWindowSampler {
+    fn tick(&mut self, time: &dyn FrameTiming) {
+        let frame_time = time.elapsed_wall_time().as_seconds();
+        let window = nanos(self.window.as_seconds());
+
+        self.past_data.push_back(frame_time);
+        self.covered_nanos += nanos(frame_time);
+
+        // Drop old frames as long as the remaining ones still cover the window. The covered
+        // time is totalled in whole nanoseconds so that it stays exact however long it runs.
+        while self.past_data.len() > 1 && self.covered_nanos - nanos(self.past_data[0]) >= window
+        {
+            self.covered_nanos -= nanos(self.past_data.pop_front().unwrap());
+        }
+    }
+    fn average_frame_rate(&self) -> f64 {
+        self.mode.average(&self.past_data)
+    }
+    /// Return true if the cached frames cover the entire window.
+    fn is_saturated(&self) -> bool {
+        self.covered_nanos >= nanos(self.window.as_seconds())
+    }
+    /// Return the number of frames currently being averaged over.
+    ///
+    /// This varies with the frame rate, see
+    /// [`window_duration`](#method.window_duration) for the fixed size of the window.
+    fn max_samples(&self) -> u32 {
+        self.past_data.len() as u32
+    }
+    fn window_duration(&self) -> Option<FloatDuration> {
+        Some(self.window)
+    }
+}
+
+impl Default for SlidingWindowSampler {
+    fn default() -> SlidingWindowSampler {
+        SlidingWindowSampler::new()
+    }
+}
+
+impl TumblingWindowSampler {
+    /// Construct a new `TumblingWindowSampler` with a default interval.
+    pub fn new() -> TumblingWindowSampler {
+        TumblingWindowSampler::with_window(FloatDuration::seconds(DEFAULT_WINDOW))
+    }
+    /// Construct a new `TumblingWindowSampler` updating once per `window` of wall time.
+    pub fn with_window(window: FloatDuration) -> TumblingWindowSampler {
+        TumblingWindowSampler {
+            bucket_samples: 0,
+            bucket_time: 0.0,
+            bucket_rate_sum: 0.0,
+            last_samples: 0,
+            last_average: None,
+            window,
+            mode: AveragingMode::default(),
+        }
+    }
+
+    /// Return the quantity being averaged.
+    pub fn averaging_mode(&self) -> AveragingMode {
+        self.mode
+    }
+    /// Set the quantity being averaged.
+    ///
+    /// The new mode takes effect at the end of the current interval.
+    pub fn set_averaging_mode(&mut self, mode: AveragingMode) -> &mut TumblingWindowSampler {
+        self.mode = mode;
+        self
+    }
+
+    fn bucket_average(&self) -> f64 {
+        match self.mode {
+            AveragingMode::FrameRate => self.bucket_rate_sum / self.bucket_samples as f64,
+            AveragingMode::FrameTime => self.bucket_samples as f64 / self.bucket_time,
+        }
+    }
+}
+
+impl FrameRateSampler for TumblingWindowSampler {
+    fn tick(&mut self, time: &dyn FrameTiming) {
+        let frame_time = time.elapsed_wall_time().as_seconds();
+
+        self.bucket_samples += 1;
+        self.bucket_time += frame_time;
+        self.bucket_rate_sum += 1.0 / frame_time;
+
+        if self.bucket_time >= self.window.as_seconds() {
+            self.last_average = Some(self.bucket_average());
+            self.last_samples = self.bucket_samples;
+            self.bucket_samples = 0;
+            self.bucket_time = 0.0;
+            self.bucket_rate_sum = 0.0;
+        }
+    }
+    fn average_frame_rate(&self) -> f64 {
+        match self.last_average {
+            Some(average) => average,
+            None => self.bucket_average(),
+        }
+    }
+    /// Return true once the first interval is complete.
+    fn is_saturated(&self) -> bool {
+        self.last_average.is_some()
+    }
+    /// Return the number of frames in the last complete interval.
+    ///
+    /// This varies with the frame rate, see
+    /// [`window_duration`](#method.window_duration) for the fixed length of the interval.
+    fn max_samples(&self) -> u32 {
+        self.last_samples
+    }
+    fn window_duration(&self) -> Option<FloatDuration> {
+        Some(self.window)
+    }
+}
+
+impl Default for TumblingWindowSampler {
+    fn default() -> TumblingWindowSampler {
+        TumblingWindowSampler::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -197,4 +875,236 @@ mod tests {
         let sampler2 = RunningAverageSampler::default().clone();
         assert_eq!(sampler2.max_samples(), DEFAULT_NUM_SAMPLES);
     }
+
+    fn frame_times_with_hitch() -> Vec<chrono::Duration> {
+        vec![
+            chrono::Duration::milliseconds(100),
+            chrono::Duration::milliseconds(100),
+            chrono::Duration::milliseconds(100),
+            chrono::Duration::milliseconds(500),
+        ]
+    }
+
+    fn run_sampler<S: FrameRateSampler>(sampler: &mut S, frame_times: &[chrono::Duration]) {
+        let mut clock = GameClock::default();
+        let step = step::VariableStep::new();
+        let mut frame_time = clock.start_wall_time();
+
+        for dt in frame_times {
+            frame_time += *dt;
+            let time = clock.tick_with_wall_time(&step, frame_time);
+            sampler.tick(&time);
+        }
+    }
+
+    #[test]
+    fn test_linear_sampler_averaging_mode() {
+        let mut sampler = LinearAverageSampler::with_max_samples(10);
+        assert_eq!(sampler.averaging_mode(), AveragingMode::FrameRate);
+        run_sampler(&mut sampler, &frame_times_with_hitch());
+
+        // (10 + 10 + 10 + 2) / 4: the long frame barely moves the average.
+        assert!(relative_eq!(sampler.average_frame_rate(), 8.0, epsilon = 1e-8));
+
+        // 4 frames / 0.8 seconds.
+        sampler.set_averaging_mode(AveragingMode::FrameTime);
+        assert_eq!(sampler.averaging_mode(), AveragingMode::FrameTime);
+        assert!(relative_eq!(sampler.average_frame_rate(), 5.0, epsilon = 1e-8));
+    }
+
+    #[test]
+    fn test_running_avg_sampler_averaging_mode() {
+        let mut sampler = RunningAverageSampler::with_max_samples(10);
+        assert_eq!(sampler.averaging_mode(), AveragingMode::FrameRate);
+        run_sampler(&mut sampler, &frame_times_with_hitch());
+
+        assert!(relative_eq!(sampler.average_frame_rate(), 8.0, epsilon = 1e-8));
+
+        sampler.set_averaging_mode(AveragingMode::FrameTime);
+        assert!(relative_eq!(sampler.average_frame_rate(), 5.0, epsilon = 1e-8));
+    }
+
+    #[test]
+    fn test_exponential_sampler() {
+        let mut clock = GameClock::default();
+        let step = step::ConstantStep::new(FloatDuration::seconds(0.05));
+        let sampler = ExponentialSampler::with_half_life(FloatDuration::seconds(0.5));
+        let mut count = counter::FrameCounter::new(20.0, sampler);
+        let start_time = clock.start_wall_time();
+        let dt = chrono::Duration::milliseconds(100);
+
+        for i in 0..5 {
+            assert!(!count.is_saturated());
+            let time = clock.tick_with_wall_time(&step, start_time + dt * (i + 1));
+            count.tick(&time);
+            assert!(relative_eq!(count.average_frame_rate(), 10.0, epsilon = 1e-8));
+        }
+        assert!(count.is_saturated());
+        assert_eq!(count.sampler().max_samples(), 5);
+
+        let sampler2 = ExponentialSampler::default().clone();
+        assert_eq!(sampler2.half_life(), FloatDuration::seconds(DEFAULT_HALF_LIFE));
+        assert_eq!(sampler2.max_samples(), 0);
+    }
+
+    #[test]
+    fn test_exponential_sampler_frame_rate_independence() {
+        // One half-life after the frame time changes, the old frame time should account
+        // for exactly half of the average at any frame rate.
+        for &frame_ms in &[5, 25, 100] {
+            let mut sampler = ExponentialSampler::with_half_life(FloatDuration::seconds(0.5));
+            sampler.set_averaging_mode(AveragingMode::FrameTime);
+
+            let mut frame_times = vec![chrono::Duration::seconds(1)];
+            let frame_dt = chrono::Duration::milliseconds(frame_ms);
+            frame_times.extend((0..500 / frame_ms).map(|_| frame_dt));
+            run_sampler(&mut sampler, &frame_times);
+
+            let expected_time = 0.5 * 1.0 + 0.5 * (frame_ms as f64 / 1000.0);
+            assert!(relative_eq!(
+                sampler.average_frame_rate(),
+                1.0 / expected_time,
+                epsilon = 1e-8
+            ));
+        }
+    }
+
+    fn frame_times_with_noise_and_hitch() -> Vec<chrono::Duration> {
+        [100, 98, 102, 100, 99, 101, 500, 100, 100]
+            .iter()
+            .map(|&ms| chrono::Duration::milliseconds(ms))
+            .collect()
+    }
+
+    #[test]
+    fn test_median_sampler() {
+        let mut sampler = MedianSampler::with_max_samples(10);
+        assert_eq!(sampler.threshold(), OutlierThreshold::Mad(DEFAULT_MAD_THRESHOLD));
+        run_sampler(&mut sampler, &frame_times_with_noise_and_hitch());
+
+        assert!(relative_eq!(sampler.average_frame_rate(), 10.0, epsilon = 1e-8));
+        assert_eq!(sampler.stutter_count(), 1);
+        assert!(!sampler.is_saturated());
+
+        sampler.reset_stutter_count();
+        assert_eq!(sampler.stutter_count(), 0);
+
+        let sampler2 = MedianSampler::default().clone();
+        assert_eq!(sampler2.max_samples(), DEFAULT_NUM_SAMPLES);
+    }
+
+    #[test]
+    fn test_trimmed_mean_sampler() {
+        let mut sampler = TrimmedMeanSampler::with_max_samples(10);
+        sampler.set_averaging_mode(AveragingMode::FrameTime);
+        run_sampler(&mut sampler, &frame_times_with_noise_and_hitch());
+
+        assert_eq!(sampler.stutter_count(), 1);
+        assert_eq!(sampler.rejected_samples(), 1);
+        assert!(relative_eq!(sampler.average_frame_rate(), 10.0, epsilon = 1e-8));
+
+        // Trimming 10% from each end of 9 samples drops the single longest
+        // and shortest frames.
+        sampler.set_threshold(OutlierThreshold::Percentile(0.1));
+        assert_eq!(sampler.rejected_samples(), 2);
+        assert!(relative_eq!(sampler.average_frame_rate(), 7.0 / 0.702, epsilon = 1e-8));
+
+        let sampler2 = TrimmedMeanSampler::default().clone();
+        assert_eq!(sampler2.max_samples(), DEFAULT_NUM_SAMPLES);
+    }
+
+    #[test]
+    #[should_panic(expected = "outlier percentile")]
+    fn test_invalid_percentile() {
+        TrimmedMeanSampler::new().set_threshold(OutlierThreshold::Percentile(0.75));
+    }
+
+    #[test]
+    #[should_panic(expected = "outlier percentile")]
+    fn test_percentile_above_one() {
+        MedianSampler::new().set_threshold(OutlierThreshold::Percentile(1.5));
+    }
+
+    #[test]
+    fn test_constant_frames_mad_floor() {
+        let ms = chrono::Duration::milliseconds;
+        let mut frame_times = vec![ms(100); 20];
+        frame_times.push(ms(101));
+        let mut median = MedianSampler::with_max_samples(64);
+        let mut trimmed = TrimmedMeanSampler::with_max_samples(64);
+        run_sampler(&mut median, &frame_times);
+        run_sampler(&mut trimmed, &frame_times);
+
+        // The deviation of constant frames is zero, but a frame 1% longer is not a stutter.
+        assert_eq!(median.stutter_count(), 0);
+        assert_eq!(trimmed.stutter_count(), 0);
+        assert_eq!(trimmed.rejected_samples(), 0);
+
+        run_sampler(&mut median, &[ms(110)]);
+        run_sampler(&mut trimmed, &[ms(110)]);
+        assert_eq!(median.stutter_count(), 1);
+        assert_eq!(trimmed.stutter_count(), 1);
+        assert_eq!(trimmed.rejected_samples(), 1);
+    }
+
+    #[test]
+    fn test_sliding_window_sampler() {
+        let mut sampler = SlidingWindowSampler::with_window(FloatDuration::seconds(0.45));
+        assert_eq!(sampler.window_duration(), Some(FloatDuration::seconds(0.45)));
+
+        run_sampler(&mut sampler, &[chrono::Duration::milliseconds(100); 4]);
+        assert!(!sampler.is_saturated());
+        assert_eq!(sampler.max_samples(), 4);
+
+        run_sampler(&mut sampler, &[chrono::Duration::milliseconds(100); 1]);
+        assert!(sampler.is_saturated());
+        assert_eq!(sampler.max_samples(), 5);
+        assert!(relative_eq!(sampler.average_frame_rate(), 10.0, epsilon = 1e-8));
+
+        // Doubling the frame rate keeps the window the same length in time.
+        run_sampler(&mut sampler, &[chrono::Duration::milliseconds(50); 10]);
+        assert!(sampler.is_saturated());
+        assert_eq!(sampler.max_samples(), 9);
+        assert!(relative_eq!(sampler.average_frame_rate(), 20.0, epsilon = 1e-8));
+
+        let sampler2 = SlidingWindowSampler::default().clone();
+        assert_eq!(sampler2.window_duration(), Some(FloatDuration::seconds(DEFAULT_WINDOW)));
+        assert!(LinearAverageSampler::new().window_duration().is_none());
+    }
+
+    #[test]
+    fn test_sliding_window_long_run() {
+        let mut sampler = SlidingWindowSampler::with_window(FloatDuration::seconds(1.0));
+        let mut frame_times = vec![chrono::Duration::seconds(30)];
+        frame_times.extend((0..1_000_000).map(|i| chrono::Duration::microseconds(7_000 + i % 3)));
+        frame_times.extend(vec![chrono::Duration::milliseconds(16); 100]);
+        run_sampler(&mut sampler, &frame_times);
+
+        // 63 frames of 16ms are the fewest that cover one second.
+        assert_eq!(sampler.num_samples(), 63);
+        assert_eq!(sampler.covered_nanos, 63 * 16_000_000);
+        assert!(sampler.is_saturated());
+        assert!(relative_eq!(sampler.average_frame_rate(), 62.5, epsilon = 1e-9));
+    }
+
+    #[test]
+    fn test_tumbling_window_sampler() {
+        let mut sampler = TumblingWindowSampler::with_window(FloatDuration::seconds(0.95));
+        sampler.set_averaging_mode(AveragingMode::FrameTime);
+
+        run_sampler(&mut sampler, &[chrono::Duration::milliseconds(100); 9]);
+        assert!(!sampler.is_saturated());
+        assert!(relative_eq!(sampler.average_frame_rate(), 10.0, epsilon = 1e-8));
+
+        run_sampler(&mut sampler, &[chrono::Duration::milliseconds(100); 1]);
+        assert!(sampler.is_saturated());
+        assert_eq!(sampler.max_samples(), 10);
+
+        // The reading holds until the next interval is complete.
+        run_sampler(&mut sampler, &[chrono::Duration::milliseconds(50); 18]);
+        assert!(relative_eq!(sampler.average_frame_rate(), 10.0, epsilon = 1e-8));
+        run_sampler(&mut sampler, &[chrono::Duration::milliseconds(50); 1]);
+        assert!(relative_eq!(sampler.average_frame_rate(), 20.0, epsilon = 1e-8));
+        assert_eq!(sampler.max_samples(), 19);
+    }
 }
@@ -0,0 +1,343 @@
+//! Handoff of frame times from a simulation thread to a render thread.
+//!
+//! When simulation and rendering run on separate threads at different rates, the render
+//! thread interpolates between the two most recent simulation frames.
+//! [`handoff`](./fn.handoff.html) splits a [`FrameRunner`](../runner/struct.FrameRunner.html)
+//! into a [`SimHandoff`](./struct.SimHandoff.html), which runs the simulation and publishes
+//! each frame, and a [`RenderHandoff`](./struct.RenderHandoff.html), which picks up the
+//! latest [`SimFrame`](./struct.SimFrame.html) and computes the interpolation alpha.
+//!
+//! Frames are passed through a triple buffer, so neither side ever blocks or waits on the
+//! other. Frames published faster than the render thread picks them up are skipped.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::thread;
+//! use game_time::{GameClock, FrameCounter, FloatDuration};
+//! use game_time::framerate::LinearAverageSampler;
+//! use game_time::handoff;
+//! use game_time::runner::FrameRunner;
+//! use game_time::step;
+//!
+//! let counter = FrameCounter::new(60.0, LinearAverageSampler::new());
+//! let runner = FrameRunner::new(GameClock::new(), counter);
+//! let (mut sim, mut render) = handoff::handoff(runner);
+//!
+//! let sim_thread = thread::spawn(move || {
+//!     for _ in 0..10 {
+//!         sim.tick(&step::ConstantStep::new(FloatDuration::milliseconds(16.0)));
+//!     }
+//! });
+//!
+//! sim_thread.join().unwrap();
+//! render.update();
+//! assert_eq!(render.frame().current().frame_number(), 10);
+//! assert_eq!(render.frame().previous().frame_number(), 9);
+//! ```
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use chrono::{self, TimeZone};
+use float_duration::FloatDuration;
+
+use clock::GameTime;
+use framerate::FrameCount;
+use runner::FrameRunner;
+use step::StatefulStep;
+
+const INDEX_MASK: usize = 0b011;
+const FRESH: usize = 0b100;
+
+/// The two most recent simulation frames.
+#[derive(Debug, Clone)]
+pub struct SimFrame<Tz: TimeZone = chrono::Local> {
+    previous: GameTime<Tz>,
+    current: GameTime<Tz>,
+}
+
+/// The simulation side of a frame handoff.
+///
+/// `SimHandoff` owns the `FrameRunner` and publishes every frame it runs.
+#[derive(Debug)]
+pub struct SimHandoff<C: FrameCount, Tz: TimeZone = chrono::Local> {
+    runner: FrameRunner<C, Tz>,
+    writer: Writer<SimFrame<Tz>>,
+}
+
+/// The render side of a frame handoff.
+#[derive(Debug)]
+pub struct RenderHandoff<Tz: TimeZone = chrono::Local> {
+    reader: Reader<SimFrame<Tz>>,
+}
+
+/// Split `runner` into the two sides of a frame handoff.
+///
+/// Until the first frame is published, the render side sees the runner's current
+/// frame time as both the previous and current frame.
+pub fn handoff<C, Tz>(runner: FrameRunner<C, Tz>) -> (SimHandoff<C, Tz>, RenderHandoff<Tz>)
+where
+    C: FrameCount,
+    Tz: TimeZone,
+{
+    let time = runner.clock().last_frame_time().clone();
+    let initial = SimFrame {
+        previous: time.clone(),
+        current: time,
+    };
+    let (writer, reader) = triple_buffer(initial);
+    (SimHandoff { runner, writer }, RenderHandoff { reader })
+}
+
+impl<Tz: TimeZone> SimFrame<Tz> {
+    /// The frame before the current one.
+    pub fn previous(&self) -> &GameTime<Tz> {
+        &self.previous
+    }
+    /// The latest completed frame.
+    pub fn current(&self) -> &GameTime<Tz> {
+        &self.current
+    }
+    /// Return the interpolation alpha between the previous and current frame at wall
+    /// time `now`.
+    ///
+    /// The alpha is the fraction of the current frame's duration that has passed since it
+    /// started, assuming the next frame will take as long as the current one. It is clamped
+    /// to `[0.0, 1.0]`.
+    pub fn alpha_at<Tz2: TimeZone>(&self, now: chrono::DateTime<Tz2>) -> f64 {
+        let interval = self.current.elapsed_wall_time().as_seconds();
+        if interval <= 0.0 {
+            return 1.0;
+        }
+        let since_start =
+            FloatDuration::from_chrono(now.signed_duration_since(self.current.frame_start_time()))
+                .as_seconds();
+        (since_start / interval).clamp(0.0, 1.0)
+    }
+}
+
+impl<C: FrameCount, Tz: TimeZone> SimHandoff<C, Tz> {
+    /// Get a reference to the contained `FrameRunner`.
+    pub fn runner(&self) -> &FrameRunner<C, Tz> {
+        &self.runner
+    }
+    /// Get a mutable reference to the contained `FrameRunner`.
+    ///
+    /// Frames ticked directly on the runner are not published, see
+    /// [`publish`](#method.publish).
+    pub fn runner_mut(&mut self) -> &mut FrameRunner<C, Tz> {
+        &mut self.runner
+    }
+    /// Mark the start of a new frame on the runner and publish it.
+    pub fn tick<S: StatefulStep>(&mut self, time_step: S) -> GameTime<Tz> {
+        let time = self.runner.tick(time_step);
+        self.publish(&time);
+        time
+    }
+    /// Mark the start of a new frame with a specified wall time and publish it.
+    pub fn tick_with_wall_time<S: StatefulStep>(
+        &mut self,
+        time_step: S,
+        frame_start: chrono::DateTime<Tz>,
+    ) -> GameTime<Tz> {
+        let time = self.runner.tick_with_wall_time(time_step, frame_start);
+        self.publish(&time);
+        time
+    }
+    /// Publish a frame, with the previously published frame as its predecessor.
+    pub fn publish(&mut self, time: &GameTime<Tz>) {
+        let previous = self.writer.last.current.clone();
+        self.writer.write(SimFrame {
+            previous,
+            current: time.clone(),
+        });
+    }
+    /// Consume the `SimHandoff`, returning the contained `FrameRunner`.
+    pub fn into_runner(self) -> FrameRunner<C, Tz> {
+        self.runner
+    }
+}
+
+impl<Tz: TimeZone> RenderHandoff<Tz> {
+    /// Pick up the latest published frame, if any.
+    ///
+    /// Returns true if a new frame was published since the last update.
+    pub fn update(&mut self) -> bool {
+        self.reader.update()
+    }
+    /// Return the latest frame picked up by [`update`](#method.update).
+    pub fn frame(&self) -> &SimFrame<Tz> {
+        self.reader.get()
+    }
+    /// Return the interpolation alpha at the start of a render frame.
+    ///
+    /// `render_time` is the render thread's own `GameTime` for the frame, which may come
+    /// from a clock in a different time zone.
+    pub fn alpha<Tz2: TimeZone>(&self, render_time: &GameTime<Tz2>) -> f64 {
+        self.frame().alpha_at(render_time.frame_start_time())
+    }
+    /// Return the interpolation alpha at the current wall time.
+    pub fn alpha_now(&self) -> f64 {
+        self.frame().alpha_at(chrono::Utc::now())
+    }
+}
+
+/// Storage shared between the two sides of a triple buffer.
+///
+/// One slot is owned by the writer, one by the reader, and the third is exchanged
+/// between them through `middle`, which holds its index and whether it holds a value
+/// not yet seen by the reader.
+struct TripleBuffer<T> {
+    slots: [UnsafeCell<T>; 3],
+    middle: AtomicUsize,
+}
+
+// The writer and reader only ever access the slot they own, and ownership of slots
+// is transferred through `middle`.
+unsafe impl<T: Send> Sync for TripleBuffer<T> {}
+
+struct Writer<T> {
+    buffer: Arc<TripleBuffer<T>>,
+    index: usize,
+    last: T,
+}
+
+struct Reader<T> {
+    buffer: Arc<TripleBuffer<T>>,
+    index: usize,
+}
+
+fn triple_buffer<T: Clone>(initial: T) -> (Writer<T>, Reader<T>) {
+    let buffer = Arc::new(TripleBuffer {
+        slots: [
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial.clone()),
+        ],
+        middle: AtomicUsize::new(1),
+    });
+    let writer = Writer {
+        buffer: buffer.clone(),
+        index: 0,
+        last: initial,
+    };
+    let reader = Reader { buffer, index: 2 };
+    (writer, reader)
+}
+
+impl<T: Clone> Writer<T> {
+    fn write(&mut self, value: T) {
+        self.last = value.clone();
+        unsafe {
+            *self.buffer.slots[self.index].get() = value;
+        }
+        let prev = self.buffer
+            .middle
+            .swap(self.index | FRESH, Ordering::AcqRel);
+        self.index = prev & INDEX_MASK;
+    }
+}
+
+impl<T> Reader<T> {
+    fn update(&mut self) -> bool {
+        if self.buffer.middle.load(Ordering::Relaxed) & FRESH == 0 {
+            return false;
+        }
+        let prev = self.buffer.middle.swap(self.index, Ordering::AcqRel);
+        self.index = prev & INDEX_MASK;
+        true
+    }
+    fn get(&self) -> &T {
+        unsafe { &*self.buffer.slots[self.index].get() }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Writer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Writer").field("last", &self.last).finish()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Reader<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Reader").field("value", self.get()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    use float_duration::FloatDuration;
+    use clock::GameClock;
+    use framerate::{FrameCounter, LinearAverageSampler};
+    use step;
+
+    fn runner() -> FrameRunner<FrameCounter<LinearAverageSampler>> {
+        let counter = FrameCounter::new(20.0, LinearAverageSampler::new());
+        FrameRunner::new(GameClock::new(), counter)
+    }
+
+    #[test]
+    fn test_handoff() {
+        let (mut sim, mut render) = handoff(runner());
+        let step = step::VariableStep::new();
+        let start_time = sim.runner().clock().start_wall_time();
+
+        assert!(!render.update());
+        assert_eq!(render.frame().current().frame_number(), 0);
+
+        for i in 1..4 {
+            let frame_time = start_time + chrono::Duration::milliseconds(100 * i);
+            sim.tick_with_wall_time(&step, frame_time);
+        }
+        assert!(render.update());
+        assert!(!render.update());
+        assert_eq!(render.frame().previous().frame_number(), 2);
+        assert_eq!(render.frame().current().frame_number(), 3);
+
+        let frame_start = render.frame().current().frame_start_time();
+        let alpha_at = |ms| {
+            render
+                .frame()
+                .alpha_at(frame_start + chrono::Duration::milliseconds(ms))
+        };
+        assert!(relative_eq!(alpha_at(25), 0.25, epsilon = 1e-9));
+        assert_eq!(alpha_at(-10), 0.0);
+        assert_eq!(alpha_at(150), 1.0);
+
+        let mut render_clock = GameClock::new();
+        let render_time = render_clock.tick_with_wall_time(
+            &step,
+            frame_start + chrono::Duration::milliseconds(50),
+        );
+        assert!(relative_eq!(render.alpha(&render_time), 0.5, epsilon = 1e-9));
+        assert_eq!(sim.into_runner().clock().current_frame_number(), 3);
+    }
+
+    #[test]
+    fn test_concurrent_handoff() {
+        let (mut sim, mut render) = handoff(runner());
+        let step = step::ConstantStep::new(FloatDuration::milliseconds(1.0));
+
+        let sim_thread = thread::spawn(move || for _ in 0..10_000 {
+            sim.tick(&step);
+        });
+
+        let mut last_frame = 0;
+        while last_frame < 10_000 {
+            render.update();
+            let frame = render.frame();
+            let current = frame.current().frame_number();
+            assert!(current >= last_frame);
+            if current > 0 {
+                assert_eq!(frame.previous().frame_number() + 1, current);
+            }
+            last_frame = current;
+        }
+        sim_thread.join().unwrap();
+    }
+}
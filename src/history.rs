@@ -0,0 +1,263 @@
+//! A history of recent frames for debugging.
+//!
+//! [`FrameHistory`](./struct.FrameHistory.html) keeps the last N `GameTime`s along with
+//! any annotations attached to each frame, and can be queried by frame number or by game
+//! time. A history can be kept by hand, or automatically by a `GameClock` through
+//! [`GameClock::enable_history`](../clock/struct.GameClock.html#method.enable_history).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use game_time::{GameClock, FloatDuration};
+//! use game_time::step;
+//!
+//! let mut clock = GameClock::new();
+//! clock.enable_history(100);
+//! let step = step::ConstantStep::new(FloatDuration::seconds(1.0));
+//!
+//! for i in 0..10 {
+//!     clock.tick(&step);
+//!     if i == 4 {
+//!         clock.history_mut().unwrap().annotate_latest("boss spawned".to_string());
+//!     }
+//! }
+//!
+//! let history = clock.history().unwrap();
+//! let frame = history.at_game_time(FloatDuration::seconds(5.5)).unwrap();
+//! assert_eq!(frame.time().frame_number(), 5);
+//! assert_eq!(frame.annotations(), &["boss spawned".to_string()]);
+//! ```
+use std::collections::VecDeque;
+use std::collections::vec_deque;
+
+use chrono::{self, TimeZone};
+use float_duration::FloatDuration;
+
+use clock::GameTime;
+
+/// A recorded frame and its annotations.
+#[derive(Debug, Clone)]
+pub struct HistoryEntry<A, Tz: TimeZone = chrono::Local> {
+    time: GameTime<Tz>,
+    annotations: Vec<A>,
+}
+
+/// A bounded history of recent frames.
+///
+/// Frames must be recorded in order. Queries by game time use a binary search over the total
+/// game time of each frame, which is correct as long as game time never runs backwards,
+/// whatever the clock multiplier was for each frame.
+#[derive(Debug, Clone)]
+pub struct FrameHistory<A = String, Tz: TimeZone = chrono::Local> {
+    entries: VecDeque<HistoryEntry<A, Tz>>,
+    max_frames: usize,
+}
+
+impl<A, Tz: TimeZone> HistoryEntry<A, Tz> {
+    /// The recorded time of the frame.
+    pub fn time(&self) -> &GameTime<Tz> {
+        &self.time
+    }
+    /// The annotations attached to the frame, in the order they were added.
+    pub fn annotations(&self) -> &[A] {
+        &self.annotations
+    }
+}
+
+impl<A, Tz: TimeZone> FrameHistory<A, Tz> {
+    /// Construct a new `FrameHistory` keeping the last `max_frames` frames.
+    pub fn new(max_frames: usize) -> FrameHistory<A, Tz> {
+        FrameHistory {
+            entries: VecDeque::with_capacity(max_frames),
+            max_frames,
+        }
+    }
+
+    /// Return the maximum number of frames kept.
+    pub fn max_frames(&self) -> usize {
+        self.max_frames
+    }
+    /// Set the maximum number of frames kept, discarding the oldest frames if needed.
+    pub fn set_max_frames(&mut self, max_frames: usize) -> &mut FrameHistory<A, Tz> {
+        self.max_frames = max_frames;
+        while self.entries.len() > max_frames {
+            self.entries.pop_front();
+        }
+        self
+    }
+    /// Return the number of frames currently kept.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /// Return true if no frames are kept.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Remove all frames.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Record a new frame, discarding the oldest frame if the history is full.
+    pub fn record(&mut self, time: &GameTime<Tz>) {
+        if self.max_frames == 0 {
+            return;
+        }
+        if self.entries.len() == self.max_frames {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(HistoryEntry {
+            time: time.clone(),
+            annotations: Vec::new(),
+        });
+    }
+    /// Attach an annotation to the frame numbered `frame_number`.
+    ///
+    /// Returns false, dropping the annotation, if the frame is not in the history.
+    pub fn annotate(&mut self, frame_number: u64, annotation: A) -> bool {
+        match self.index_of(frame_number) {
+            Some(index) => {
+                self.entries[index].annotations.push(annotation);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Attach an annotation to the most recent frame.
+    ///
+    /// Returns false, dropping the annotation, if the history is empty.
+    pub fn annotate_latest(&mut self, annotation: A) -> bool {
+        match self.entries.back_mut() {
+            Some(entry) => {
+                entry.annotations.push(annotation);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return the most recent frame.
+    pub fn latest(&self) -> Option<&HistoryEntry<A, Tz>> {
+        self.entries.back()
+    }
+    /// Return the oldest frame kept.
+    pub fn oldest(&self) -> Option<&HistoryEntry<A, Tz>> {
+        self.entries.front()
+    }
+    /// Return an iterator over the kept frames, oldest first.
+    pub fn iter(&self) -> vec_deque::Iter<'_, HistoryEntry<A, Tz>> {
+        self.entries.iter()
+    }
+    /// Return the frame numbered `frame_number`, if it is kept.
+    pub fn get(&self, frame_number: u64) -> Option<&HistoryEntry<A, Tz>> {
+        self.index_of(frame_number).map(|index| &self.entries[index])
+    }
+    /// Return the frame that was active at game time `time`.
+    ///
+    /// This is the last frame that started at or before `time`. Returns `None` if `time`
+    /// is before the oldest frame kept.
+    pub fn at_game_time(&self, time: FloatDuration) -> Option<&HistoryEntry<A, Tz>> {
+        self.active_index(time).map(|index| &self.entries[index])
+    }
+    /// Return the frames that were active at any point in the game time range
+    /// `[start, end]`, oldest first.
+    pub fn in_game_time_range(
+        &self,
+        start: FloatDuration,
+        end: FloatDuration,
+    ) -> vec_deque::Iter<'_, HistoryEntry<A, Tz>> {
+        let first = self.active_index(start).unwrap_or(0);
+        let last = self.entries
+            .partition_point(|entry| entry.time.total_game_time() <= end);
+        let last = last.max(first);
+        self.entries.range(first..last)
+    }
+
+    fn index_of(&self, frame_number: u64) -> Option<usize> {
+        self.entries
+            .binary_search_by_key(&frame_number, |entry| entry.time.frame_number())
+            .ok()
+    }
+
+    fn active_index(&self, time: FloatDuration) -> Option<usize> {
+        let count = self.entries
+            .partition_point(|entry| entry.time.total_game_time() <= time);
+        count.checked_sub(1)
+    }
+}
+
+impl<'a, A, Tz: TimeZone> IntoIterator for &'a FrameHistory<A, Tz> {
+    type Item = &'a HistoryEntry<A, Tz>;
+    type IntoIter = vec_deque::Iter<'a, HistoryEntry<A, Tz>>;
+
+    fn into_iter(self) -> vec_deque::Iter<'a, HistoryEntry<A, Tz>> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::GameClock;
+    use step;
+
+    #[test]
+    fn test_record_and_annotate() {
+        let mut clock = GameClock::new();
+        let mut history = FrameHistory::new(5);
+        let step = step::ConstantStep::new(FloatDuration::seconds(1.0));
+
+        assert!(!history.annotate_latest(0));
+        for _ in 0..8 {
+            history.record(&clock.tick(&step));
+        }
+        assert_eq!(history.len(), 5);
+        assert_eq!(history.oldest().unwrap().time().frame_number(), 4);
+        assert_eq!(history.latest().unwrap().time().frame_number(), 8);
+
+        assert!(history.annotate(6, 1));
+        assert!(history.annotate(6, 2));
+        assert!(!history.annotate(2, 3));
+        assert!(history.annotate_latest(4));
+        assert_eq!(history.get(6).unwrap().annotations(), &[1, 2]);
+        assert_eq!(history.get(8).unwrap().annotations(), &[4]);
+        assert!(history.get(3).is_none());
+
+        history.set_max_frames(2);
+        let frames: Vec<_> = history.iter().map(|e| e.time().frame_number()).collect();
+        assert_eq!(frames, vec![7, 8]);
+    }
+
+    #[test]
+    fn test_game_time_queries() {
+        let mut clock = GameClock::new();
+        clock.enable_history(100);
+        let step = step::ConstantStep::new(FloatDuration::seconds(1.0));
+
+        // Frames 1-4 at 1x, 5-6 paused, 7-8 at 4x.
+        for multiplier in &[1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 4.0, 4.0] {
+            clock.set_clock_multiplier(*multiplier);
+            clock.tick(&step);
+        }
+        let history = clock.history().unwrap();
+        let frame_at = |t| {
+            history
+                .at_game_time(FloatDuration::seconds(t))
+                .map(|e| e.time().frame_number())
+        };
+
+        assert_eq!(frame_at(0.5), None);
+        assert_eq!(frame_at(1.0), Some(1));
+        assert_eq!(frame_at(3.5), Some(3));
+        assert_eq!(frame_at(4.0), Some(6));
+        assert_eq!(frame_at(7.9), Some(6));
+        assert_eq!(frame_at(8.0), Some(7));
+        assert_eq!(frame_at(100.0), Some(8));
+
+        let range: Vec<_> = history
+            .in_game_time_range(FloatDuration::seconds(3.5), FloatDuration::seconds(8.0))
+            .map(|e| e.time().frame_number())
+            .collect();
+        assert_eq!(range, vec![3, 4, 5, 6, 7]);
+    }
+}
@@ -0,0 +1,204 @@
+//! Interpolation of simulation state for rendering.
+//!
+//! When the simulation runs at a fixed rate, rendering smoothly between simulation frames
+//! requires the previous and current state along with an interpolation alpha.
+//! [`Interpolated`](./struct.Interpolated.html) keeps the two states, and blends them with
+//! the [`Lerp`](./trait.Lerp.html) trait.
+//!
+//! In a single-threaded loop, the alpha for a render frame is produced by
+//! [`FixedUpdate::alpha`](../runner/struct.FixedUpdate.html#method.alpha) alongside the fixed
+//! updates. When simulation and rendering run on separate threads, it is produced by the
+//! [`handoff`](../handoff/index.html) module, through
+//! [`RenderHandoff::alpha`](../handoff/struct.RenderHandoff.html#method.alpha) or
+//! [`SimFrame::alpha_at`](../handoff/struct.SimFrame.html#method.alpha_at).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use game_time::interpolate::Interpolated;
+//!
+//! let mut position = Interpolated::new([0.0, 0.0]);
+//! assert_eq!(position.get(0.5), [0.0, 0.0]);
+//!
+//! position.push([10.0, 4.0]);
+//! assert_eq!(position.get(0.5), [5.0, 2.0]);
+//!
+//! // Teleporting skips the interpolation from the old position.
+//! position.reset([100.0, 100.0]);
+//! assert_eq!(position.get(0.5), [100.0, 100.0]);
+//! ```
+use std::array;
+use std::mem;
+
+use float_duration::FloatDuration;
+
+/// Linear interpolation between two values.
+pub trait Lerp {
+    /// Interpolate between `self` at an alpha of `0.0` and `other` at an alpha of `1.0`.
+    fn lerp(&self, other: &Self, alpha: f64) -> Self;
+}
+
+/// The previous and current simulation state of a value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interpolated<T> {
+    previous: T,
+    current: T,
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &f32, alpha: f64) -> f32 {
+        self + (other - self) * alpha as f32
+    }
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, other: &f64, alpha: f64) -> f64 {
+        self + (other - self) * alpha
+    }
+}
+
+impl Lerp for FloatDuration {
+    fn lerp(&self, other: &FloatDuration, alpha: f64) -> FloatDuration {
+        *self + (*other - *self) * alpha
+    }
+}
+
+impl<T: Lerp, const N: usize> Lerp for [T; N] {
+    fn lerp(&self, other: &[T; N], alpha: f64) -> [T; N] {
+        array::from_fn(|i| self[i].lerp(&other[i], alpha))
+    }
+}
+
+impl<T> Interpolated<T> {
+    /// Construct a new `Interpolated` with `initial` as both the previous and current state.
+    pub fn new(initial: T) -> Interpolated<T>
+    where
+        T: Clone,
+    {
+        Interpolated {
+            previous: initial.clone(),
+            current: initial,
+        }
+    }
+
+    /// Return the state of the previous simulation frame.
+    pub fn previous(&self) -> &T {
+        &self.previous
+    }
+    /// Return the state of the current simulation frame.
+    pub fn current(&self) -> &T {
+        &self.current
+    }
+
+    /// Push the state of a new simulation frame, making the current state the previous one.
+    pub fn push(&mut self, state: T) {
+        self.previous = mem::replace(&mut self.current, state);
+    }
+    /// Set both the previous and current state to `state`.
+    ///
+    /// Use this when the value jumps, such as on a teleport, so that it is not interpolated
+    /// from its old state.
+    pub fn reset(&mut self, state: T)
+    where
+        T: Clone,
+    {
+        self.previous = state.clone();
+        self.current = state;
+    }
+
+    /// Return the state interpolated between the previous and current state by `alpha`.
+    ///
+    /// `alpha` is clamped to `[0.0, 1.0]`.
+    pub fn get(&self, alpha: f64) -> T
+    where
+        T: Lerp,
+    {
+        self.previous.lerp(&self.current, alpha.clamp(0.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono;
+    use clock::GameClock;
+    use framerate::{FrameCounter, LinearAverageSampler};
+    use handoff;
+    use runner::{FixedUpdate, FrameRunner};
+    use step;
+
+    #[test]
+    fn test_lerp() {
+        assert_eq!(1.0f32.lerp(&3.0, 0.25), 1.5);
+        assert_eq!((-2.0f64).lerp(&2.0, 0.75), 1.0);
+        assert_eq!(
+            FloatDuration::seconds(1.0).lerp(&FloatDuration::seconds(2.0), 0.5),
+            FloatDuration::seconds(1.5)
+        );
+        assert_eq!([[0.0, 2.0], [4.0, 8.0]].lerp(&[[2.0, 2.0], [0.0, 0.0]], 0.5), [
+            [1.0, 2.0],
+            [2.0, 4.0],
+        ]);
+    }
+
+    #[test]
+    fn test_interpolated() {
+        let mut value = Interpolated::new(1.0);
+        assert_eq!(value.get(0.0), 1.0);
+        assert_eq!(value.get(1.0), 1.0);
+
+        value.push(3.0);
+        value.push(5.0);
+        assert_eq!(*value.previous(), 3.0);
+        assert_eq!(*value.current(), 5.0);
+        assert_eq!(value.get(0.5), 4.0);
+        assert_eq!(value.get(-1.0), 3.0);
+        assert_eq!(value.get(2.0), 5.0);
+
+        value.reset(-1.0);
+        assert_eq!(value.get(0.5), -1.0);
+    }
+
+    #[test]
+    fn test_fixed_update_alpha() {
+        let mut clock = GameClock::new();
+        let frame = step::ConstantStep::new(FloatDuration::seconds(0.625));
+        let mut fixed = FixedUpdate::new(FloatDuration::seconds(0.25));
+        let mut position = Interpolated::new(0.0);
+        let mut simulated = 0.0;
+
+        let mut rendered = Vec::new();
+        for _ in 0..2 {
+            let time = clock.tick(&frame);
+            for _ in 0..fixed.tick(&time) {
+                simulated += 1.0;
+                position.push(simulated);
+            }
+            rendered.push(position.get(fixed.alpha()));
+        }
+        // Rendering trails the simulation by one step, so after two and a half steps it is
+        // halfway from the first state to the second, and after five it shows the fourth.
+        assert_eq!(rendered, vec![1.5, 4.0]);
+    }
+
+    #[test]
+    fn test_handoff_alpha() {
+        let counter = FrameCounter::new(60.0, LinearAverageSampler::new());
+        let clock = GameClock::new();
+        let start = clock.start_wall_time();
+        let (mut sim, mut render) = handoff::handoff(FrameRunner::new(clock, counter));
+        let step = step::VariableStep::new();
+        let mut position = Interpolated::new(0.0);
+
+        for i in 1..3 {
+            let frame_start = start + chrono::Duration::milliseconds(20 * i);
+            sim.tick_with_wall_time(&step, frame_start);
+            position.push(i as f64 * 10.0);
+        }
+        assert!(render.update());
+
+        let render_time = start + chrono::Duration::milliseconds(45);
+        let alpha = render.frame().alpha_at(render_time);
+        assert!(relative_eq!(position.get(alpha), 12.5, epsilon = 1e-9));
+    }
+}
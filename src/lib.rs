@@ -31,8 +31,10 @@
 //! For each frame, a [`TimeStep`](step/trait.TimeStep.html) is passed to `GameClock` in order
 //! to advance the frame. This allows the frame rate to be changed at any time, and allows different
 //! kinds of time steps (fixed, variable and a constant step are supported by default) to be used
-//! based on what is most useful. Together, these objects combine to form a convenient but
-//! flexible framework for time progression.
+//! based on what is most useful. Steps that keep state between frames implement
+//! [`StatefulStep`](step/trait.StatefulStep.html) and are passed by mutable reference.
+//! Together, these objects combine to form a convenient but flexible framework for time
+//! progression.
 //!
 //! # Examples
 //!
@@ -80,14 +82,29 @@ extern crate float_duration;
 #[macro_use]
 extern crate approx;
 
+pub mod calendar;
 pub mod clock;
+pub mod compare;
+pub mod cooldown;
+pub mod daycycle;
+pub mod debug;
+pub mod framelog;
 pub mod framerate;
+pub mod handoff;
+pub mod history;
+pub mod interpolate;
+pub mod profile;
+pub mod ratelimit;
 pub mod runner;
+pub mod shared;
+pub mod smooth;
+pub mod stagger;
 pub mod step;
+pub mod trace;
 
-pub use self::clock::{GameTime, GameClock};
+pub use self::clock::{GameTime, GameClock, FrameTiming};
 pub use self::framerate::{FrameCounter, FrameCount, FrameRateSampler};
 pub use self::runner::FrameRunner;
-pub use self::step::TimeStep;
+pub use self::step::{StatefulStep, TimeStep};
 
 pub use float_duration::FloatDuration;
@@ -0,0 +1,492 @@
+//! Lightweight intra-frame profiling.
+//!
+//! A [`Profiler`](./struct.Profiler.html) records named, nestable scopes within each frame.
+//! Scopes are opened with [`Profiler::scope`](./struct.Profiler.html#method.scope) and closed
+//! when the returned guard is dropped. Scope times are measured from the start of the frame
+//! using
+//! [`GameTime::elapsed_time_since_frame_start`](../clock/struct.GameTime.html#method.elapsed_time_since_frame_start),
+//! and each frame produces a [`FrameProfile`](./struct.FrameProfile.html) tree keyed by its
+//! frame number. Profiles record frame start times in the time zone of the profiled clock.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use game_time::{GameClock, FloatDuration};
+//! use game_time::profile::Profiler;
+//! use game_time::step;
+//!
+//! let mut clock = GameClock::new();
+//! let profiler = Profiler::new();
+//! let step = step::ConstantStep::new(FloatDuration::milliseconds(16.0));
+//!
+//! for _ in 0..3 {
+//!     let time = clock.tick(&step);
+//!     profiler.begin_frame(&time);
+//!     {
+//!         let _update = profiler.scope("update");
+//!         let _physics = profiler.scope("physics");
+//!     }
+//!     let _render = profiler.scope("render");
+//! }
+//! profiler.end_frame();
+//!
+//! let frame = profiler.last_frame().unwrap();
+//! assert_eq!(frame.frame_number(), 3);
+//! assert_eq!(frame.roots().count(), 2);
+//! assert!(profiler.average("physics").is_some());
+//! ```
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{self, TimeZone};
+use float_duration::FloatDuration;
+
+use clock::GameTime;
+use framerate::sample::DEFAULT_NUM_SAMPLES;
+
+/// The default number of frame profiles kept by a `Profiler`.
+pub const DEFAULT_MAX_FRAMES: usize = 64;
+
+/// The timing of a single profiled scope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeRecord {
+    name: String,
+    parent: Option<usize>,
+    depth: u32,
+    start: FloatDuration,
+    duration: FloatDuration,
+}
+
+/// The profiled scopes of a single frame.
+///
+/// Scopes are stored in the order they were opened, with each scope
+/// referring to its parent by index.
+#[derive(Debug, Clone)]
+pub struct FrameProfile<Tz: TimeZone = chrono::Local> {
+    frame_number: u64,
+    frame_start_time: chrono::DateTime<Tz>,
+    scopes: Vec<ScopeRecord>,
+}
+
+/// A per-frame scope profiler.
+///
+/// `Profiler` uses interior mutability so that scopes can be opened from anywhere with a
+/// shared reference. It is not thread-safe.
+///
+/// Call [`begin_frame`](#method.begin_frame) at the start of every frame with the frame's
+/// `GameTime`. Scopes opened before the first frame are not recorded. A scope whose guard
+/// outlives the frame it was opened in is closed and recorded at the end of that frame, and
+/// dropping the guard later has no effect.
+#[derive(Debug)]
+pub struct Profiler<Tz: TimeZone = chrono::Local> {
+    state: RefCell<ProfilerState<Tz>>,
+}
+
+/// A guard for an open profiler scope.
+///
+/// The scope is closed when the guard is dropped.
+#[derive(Debug)]
+#[must_use = "the scope is closed as soon as the guard is dropped"]
+pub struct ProfileScope<'a, Tz: 'a + TimeZone = chrono::Local> {
+    profiler: &'a Profiler<Tz>,
+    frame_number: u64,
+    index: Option<usize>,
+}
+
+#[derive(Debug)]
+struct ProfilerState<Tz: TimeZone> {
+    current: Option<(GameTime<Tz>, FrameProfile<Tz>)>,
+    open_scopes: Vec<usize>,
+    history: VecDeque<FrameProfile<Tz>>,
+    max_frames: usize,
+    averages: HashMap<String, ScopeAverage>,
+    average_samples: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ScopeAverage {
+    samples: u32,
+    average: f64,
+}
+
+impl ScopeRecord {
+    /// The name of the scope.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// The index of the enclosing scope within the frame, if any.
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+    /// The nesting depth of the scope, with `0` for top-level scopes.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+    /// The wall time from the start of the frame to the start of the scope.
+    pub fn start(&self) -> FloatDuration {
+        self.start
+    }
+    /// The wall time from the start of the frame to the end of the scope.
+    pub fn end(&self) -> FloatDuration {
+        self.start + self.duration
+    }
+    /// The wall time spent in the scope, including any nested scopes.
+    pub fn duration(&self) -> FloatDuration {
+        self.duration
+    }
+}
+
+impl<Tz: TimeZone> FrameProfile<Tz> {
+    /// The number of the profiled frame.
+    pub fn frame_number(&self) -> u64 {
+        self.frame_number
+    }
+    /// The wall time at the start of the profiled frame.
+    pub fn frame_start_time(&self) -> chrono::DateTime<Tz> {
+        self.frame_start_time.clone()
+    }
+    /// All scopes in the frame, in the order they were opened.
+    pub fn scopes(&self) -> &[ScopeRecord] {
+        &self.scopes
+    }
+    /// The top-level scopes of the frame.
+    pub fn roots(&self) -> impl Iterator<Item = &ScopeRecord> {
+        self.scopes.iter().filter(|scope| scope.parent.is_none())
+    }
+    /// The scopes directly nested within the scope at `index`.
+    pub fn children(&self, index: usize) -> impl Iterator<Item = &ScopeRecord> {
+        self.scopes
+            .iter()
+            .filter(move |scope| scope.parent == Some(index))
+    }
+    /// The total time spent in all scopes named `name` in the frame.
+    pub fn total_time(&self, name: &str) -> FloatDuration {
+        self.scopes
+            .iter()
+            .filter(|scope| scope.name == name)
+            .fold(FloatDuration::zero(), |sum, scope| sum + scope.duration)
+    }
+}
+
+impl<Tz: TimeZone> PartialEq for FrameProfile<Tz> {
+    fn eq(&self, other: &FrameProfile<Tz>) -> bool {
+        self.frame_number == other.frame_number &&
+            self.frame_start_time == other.frame_start_time &&
+            self.scopes == other.scopes
+    }
+}
+
+impl<Tz: TimeZone> Profiler<Tz> {
+    /// Construct a new `Profiler` keeping a default number of frames.
+    pub fn new() -> Profiler<Tz> {
+        Profiler::with_max_frames(DEFAULT_MAX_FRAMES)
+    }
+    /// Construct a new `Profiler` keeping the last `max_frames` frame profiles.
+    pub fn with_max_frames(max_frames: usize) -> Profiler<Tz> {
+        Profiler {
+            state: RefCell::new(ProfilerState {
+                current: None,
+                open_scopes: Vec::new(),
+                history: VecDeque::with_capacity(max_frames),
+                max_frames,
+                averages: HashMap::new(),
+                average_samples: DEFAULT_NUM_SAMPLES,
+            }),
+        }
+    }
+
+    /// Return the number of frame profiles kept.
+    pub fn max_frames(&self) -> usize {
+        self.state.borrow().max_frames
+    }
+    /// Return the number of frames each scope's rolling average is computed over.
+    pub fn average_samples(&self) -> u32 {
+        self.state.borrow().average_samples
+    }
+    /// Set the number of frames each scope's rolling average is computed over.
+    pub fn set_average_samples(&self, samples: u32) -> &Profiler<Tz> {
+        self.state.borrow_mut().average_samples = samples;
+        self
+    }
+
+    /// Start profiling a new frame.
+    ///
+    /// If a frame is currently being profiled, it is ended first.
+    pub fn begin_frame(&self, time: &GameTime<Tz>) {
+        self.end_frame();
+
+        let mut state = self.state.borrow_mut();
+        let profile = FrameProfile {
+            frame_number: time.frame_number(),
+            frame_start_time: time.frame_start_time(),
+            scopes: Vec::new(),
+        };
+        state.current = Some((time.clone(), profile));
+    }
+    /// Finish profiling the current frame, storing its profile.
+    ///
+    /// Any scopes still open are closed at the current time. Does nothing if
+    /// no frame is being profiled.
+    pub fn end_frame(&self) {
+        let mut state = self.state.borrow_mut();
+        let (time, mut profile) = match state.current.take() {
+            Some(current) => current,
+            None => return,
+        };
+
+        let now = time.elapsed_time_since_frame_start();
+        for index in state.open_scopes.drain(..) {
+            let scope = &mut profile.scopes[index];
+            scope.duration = now - scope.start;
+        }
+        state.record_averages(&profile);
+
+        if state.max_frames > 0 {
+            if state.history.len() == state.max_frames {
+                state.history.pop_front();
+            }
+            state.history.push_back(profile);
+        }
+    }
+    /// Open a named scope, returning a guard that closes it when dropped.
+    ///
+    /// Scopes opened while another scope is open are nested within it.
+    pub fn scope(&self, name: &str) -> ProfileScope<'_, Tz> {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+
+        let (frame_number, start, scopes) = match state.current {
+            Some((ref time, ref mut profile)) => (
+                profile.frame_number,
+                time.elapsed_time_since_frame_start(),
+                &mut profile.scopes,
+            ),
+            None => {
+                return ProfileScope {
+                    profiler: self,
+                    frame_number: 0,
+                    index: None,
+                }
+            }
+        };
+
+        let parent = state.open_scopes.last().cloned();
+        let index = scopes.len();
+        scopes.push(ScopeRecord {
+            name: name.to_string(),
+            parent,
+            depth: state.open_scopes.len() as u32,
+            start,
+            duration: FloatDuration::zero(),
+        });
+        state.open_scopes.push(index);
+
+        ProfileScope {
+            profiler: self,
+            frame_number,
+            index: Some(index),
+        }
+    }
+
+    /// Return the profile of the frame numbered `frame_number`, if it is still kept.
+    pub fn frame(&self, frame_number: u64) -> Option<FrameProfile<Tz>> {
+        self.state
+            .borrow()
+            .history
+            .iter()
+            .find(|profile| profile.frame_number == frame_number)
+            .cloned()
+    }
+    /// Return the profile of the most recently finished frame.
+    pub fn last_frame(&self) -> Option<FrameProfile<Tz>> {
+        self.state.borrow().history.back().cloned()
+    }
+    /// Return all kept frame profiles, oldest first.
+    pub fn frames(&self) -> Vec<FrameProfile<Tz>> {
+        self.state.borrow().history.iter().cloned().collect()
+    }
+    /// Remove and return all kept frame profiles, oldest first.
+    pub fn drain_frames(&self) -> Vec<FrameProfile<Tz>> {
+        self.state.borrow_mut().history.drain(..).collect()
+    }
+    /// Return the rolling average time per frame spent in scopes named `name`.
+    ///
+    /// Only frames in which the scope was opened are averaged.
+    pub fn average(&self, name: &str) -> Option<FloatDuration> {
+        self.state
+            .borrow()
+            .averages
+            .get(name)
+            .map(|avg| FloatDuration::seconds(avg.average))
+    }
+    /// Return the rolling averages of all scope names seen.
+    pub fn averages(&self) -> Vec<(String, FloatDuration)> {
+        self.state
+            .borrow()
+            .averages
+            .iter()
+            .map(|(name, avg)| (name.clone(), FloatDuration::seconds(avg.average)))
+            .collect()
+    }
+
+    fn close_scope(&self, frame_number: u64, index: usize) {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+
+        if let Some((ref time, ref mut profile)) = state.current {
+            if profile.frame_number != frame_number {
+                return;
+            }
+            if let Some(pos) = state.open_scopes.iter().rposition(|&i| i == index) {
+                // Close any inner scopes that are still open along with this one.
+                let now = time.elapsed_time_since_frame_start();
+                for i in state.open_scopes.drain(pos..) {
+                    let scope = &mut profile.scopes[i];
+                    scope.duration = now - scope.start;
+                }
+            }
+        }
+    }
+}
+
+impl<Tz: TimeZone> Default for Profiler<Tz> {
+    fn default() -> Profiler<Tz> {
+        Profiler::new()
+    }
+}
+
+impl<Tz: TimeZone> ProfilerState<Tz> {
+    fn record_averages(&mut self, profile: &FrameProfile<Tz>) {
+        let mut totals: HashMap<&str, f64> = HashMap::new();
+        for scope in &profile.scopes {
+            *totals.entry(&scope.name).or_insert(0.0) += scope.duration.as_seconds();
+        }
+
+        let max_samples = self.average_samples;
+        for (name, total) in totals {
+            let avg = self.averages
+                .entry(name.to_string())
+                .or_insert(ScopeAverage {
+                    samples: 0,
+                    average: 0.0,
+                });
+            if avg.samples < max_samples {
+                avg.samples += 1;
+            }
+            let n = avg.samples.max(1) as f64;
+            avg.average = (avg.average * (n - 1.0) + total) / n;
+        }
+    }
+}
+
+impl<'a, Tz: TimeZone> Drop for ProfileScope<'a, Tz> {
+    fn drop(&mut self) {
+        if let Some(index) = self.index {
+            self.profiler.close_scope(self.frame_number, index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time;
+
+    use clock::GameClock;
+    use step;
+
+    #[test]
+    fn test_scope_tree() {
+        let mut clock = GameClock::new();
+        let profiler = Profiler::with_max_frames(2);
+        let step = step::VariableStep::new();
+
+        for _ in 0..3 {
+            let time = clock.tick(&step);
+            profiler.begin_frame(&time);
+            let _update = profiler.scope("update");
+            {
+                let _physics = profiler.scope("physics");
+                thread::sleep(time::Duration::from_millis(2));
+            }
+            let _ai = profiler.scope("ai");
+        }
+        profiler.end_frame();
+
+        assert_eq!(profiler.frames().len(), 2);
+        assert!(profiler.frame(1).is_none());
+
+        let frame = profiler.frame(3).unwrap();
+        assert_eq!(frame.frame_number(), 3);
+        assert_eq!(frame.scopes().len(), 3);
+        let roots: Vec<_> = frame.roots().map(|s| s.name().to_string()).collect();
+        assert_eq!(roots, vec!["update"]);
+        let children: Vec<_> = frame.children(0).map(|s| s.name().to_string()).collect();
+        assert_eq!(children, vec!["physics", "ai"]);
+
+        let update = &frame.scopes()[0];
+        let physics = &frame.scopes()[1];
+        assert_eq!(physics.depth(), 1);
+        assert_eq!(physics.parent(), Some(0));
+        assert!(physics.duration() >= FloatDuration::milliseconds(2.0));
+        assert!(update.duration() >= physics.duration());
+        assert!(physics.start() >= update.start());
+        assert!(physics.end() <= update.end());
+
+        let avg = profiler.average("physics").unwrap();
+        assert!(avg >= FloatDuration::milliseconds(2.0));
+        assert!(profiler.average("render").is_none());
+        assert_eq!(profiler.averages().len(), 3);
+
+        assert_eq!(profiler.drain_frames().len(), 2);
+        assert!(profiler.last_frame().is_none());
+    }
+
+    #[test]
+    fn test_scope_outside_frame() {
+        let mut clock = GameClock::new();
+        let step = step::VariableStep::new();
+        let profiler = Profiler::default();
+        {
+            let _scope = profiler.scope("ignored");
+        }
+
+        profiler.begin_frame(&clock.tick(&step));
+        let stale = profiler.scope("stale");
+        profiler.begin_frame(&clock.tick(&step));
+        let scope = profiler.scope("frame");
+        drop(stale);
+        let _inner = profiler.scope("inner");
+        drop(scope);
+        profiler.end_frame();
+
+        let first = profiler.frame(1).unwrap();
+        assert_eq!(first.scopes().len(), 1);
+        assert_eq!(first.scopes()[0].name(), "stale");
+
+        // Closing a scope also closes the scopes still open within it.
+        let frame = profiler.frame(2).unwrap();
+        assert_eq!(frame.scopes().len(), 2);
+        assert_eq!(frame.scopes()[0].name(), "frame");
+        assert_eq!(frame.scopes()[1].parent(), Some(0));
+        assert!(relative_eq!(
+            frame.scopes()[1].end().as_seconds(),
+            frame.scopes()[0].end().as_seconds(),
+            epsilon = 1e-8
+        ));
+    }
+
+    #[test]
+    fn test_time_zone() {
+        let mut clock = GameClock::with_time_zone(chrono::Utc);
+        let profiler = Profiler::new();
+        let time = clock.tick(&step::VariableStep::new());
+        profiler.begin_frame(&time);
+        profiler.end_frame();
+
+        let frame = profiler.last_frame().unwrap();
+        assert_eq!(frame.frame_start_time(), time.frame_start_time());
+        assert_eq!(profiler.frame(1), Some(frame));
+    }
+}
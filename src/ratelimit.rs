@@ -0,0 +1,384 @@
+//! Periodic timers and rate limiting in game time, wall time or frames.
+//!
+//! Three primitives are provided, each measuring time with a [`Period`](./enum.Period.html):
+//!
+//! - [`Every`](./struct.Every.html) reports how many periods elapsed each frame, for
+//!   periodic work such as autosaves or network heartbeats.
+//! - [`RateLimiter`](./struct.RateLimiter.html) throttles requests to one per period, with
+//!   an optional burst budget.
+//! - [`Debounce`](./struct.Debounce.html) fires once a period has passed without being
+//!   triggered again.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use game_time::{GameClock, FloatDuration};
+//! use game_time::ratelimit::{Every, Period};
+//! use game_time::step;
+//!
+//! let mut clock = GameClock::new();
+//! let mut autosave = Every::new(Period::GameTime(FloatDuration::minutes(5.0)));
+//! let step = step::ConstantStep::new(FloatDuration::minutes(2.0));
+//!
+//! autosave.tick(clock.last_frame_time());
+//! let mut saves = 0;
+//! for _ in 0..10 {
+//!     let time = clock.tick(&step);
+//!     saves += autosave.tick(&time);
+//! }
+//! assert_eq!(saves, 4);
+//! ```
+use chrono::TimeZone;
+use float_duration::FloatDuration;
+
+use clock::GameTime;
+
+/// The length of a period and the time it is measured in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    /// A period of game time, which follows the clock multiplier and pauses.
+    GameTime(FloatDuration),
+    /// A period of wall time, measured from frame start times.
+    WallTime(FloatDuration),
+    /// A period of a number of frames.
+    Frames(u64),
+}
+
+/// A periodic timer.
+///
+/// Periods are counted from the first frame passed to [`tick`](#method.tick), so the timer
+/// does not drift when frames do not line up with the period.
+#[derive(Debug, Clone)]
+pub struct Every {
+    period: Period,
+    anchor: Option<f64>,
+    count: u64,
+    elapsed: u64,
+}
+
+/// A throttle allowing one request per period, with a burst budget.
+///
+/// The budget is refilled by one request per period up to the burst size. A burst size of
+/// `1` gives plain throttling. The budget starts full.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    period: Period,
+    burst: u32,
+    budget: f64,
+    last_position: Option<f64>,
+}
+
+/// A debouncer firing once a period passes without a new trigger.
+#[derive(Debug, Clone)]
+pub struct Debounce {
+    period: Period,
+    last_trigger: Option<f64>,
+}
+
+impl Period {
+    /// Return the length of the period in its units: seconds or frames.
+    fn length(&self) -> f64 {
+        match *self {
+            Period::GameTime(duration) | Period::WallTime(duration) => duration.as_seconds(),
+            Period::Frames(frames) => frames as f64,
+        }
+    }
+    /// Return the position of the frame `time` in the period's units.
+    fn position<Tz: TimeZone>(&self, time: &GameTime<Tz>) -> f64 {
+        match *self {
+            Period::GameTime(_) => time.total_game_time().as_seconds(),
+            Period::WallTime(_) => time.total_wall_time().as_seconds(),
+            Period::Frames(_) => time.frame_number() as f64,
+        }
+    }
+    /// Panic unless the period has a positive length.
+    fn assert_positive(&self) {
+        assert!(self.length() > 0.0, "period must be positive, got {:?}", self);
+    }
+    /// Return the number of whole periods between `start` and `end`.
+    fn periods_between(&self, start: f64, end: f64) -> u64 {
+        ((end - start) / self.length()).max(0.0).floor() as u64
+    }
+}
+
+impl Every {
+    /// Construct a new `Every` firing once every `period`.
+    ///
+    /// Panics if `period` is not positive.
+    pub fn new(period: Period) -> Every {
+        period.assert_positive();
+        Every {
+            period,
+            anchor: None,
+            count: 0,
+            elapsed: 0,
+        }
+    }
+
+    /// Return the period of the timer.
+    pub fn period(&self) -> Period {
+        self.period
+    }
+    /// Set the period of the timer.
+    ///
+    /// Periods are counted again from the next frame passed to `tick`, as after `reset`.
+    /// Panics if `period` is not positive.
+    pub fn set_period(&mut self, period: Period) -> &mut Every {
+        period.assert_positive();
+        self.period = period;
+        self.reset();
+        self
+    }
+    /// Restart the timer from the next frame passed to `tick`.
+    pub fn reset(&mut self) {
+        self.anchor = None;
+        self.count = 0;
+        self.elapsed = 0;
+    }
+
+    /// Update the timer for a new frame, returning the number of periods elapsed since
+    /// the previous frame.
+    ///
+    /// The first frame starts the timer and returns `0`.
+    pub fn tick<Tz: TimeZone>(&mut self, time: &GameTime<Tz>) -> u64 {
+        let position = self.period.position(time);
+        self.elapsed = match self.anchor {
+            Some(anchor) => {
+                let count = self.period.periods_between(anchor, position);
+                let elapsed = count.saturating_sub(self.count);
+                self.count = self.count.max(count);
+                elapsed
+            }
+            None => {
+                self.anchor = Some(position);
+                0
+            }
+        };
+        self.elapsed
+    }
+    /// Return the number of periods elapsed in the last frame.
+    pub fn elapsed(&self) -> u64 {
+        self.elapsed
+    }
+    /// Return the total number of periods elapsed since the timer started.
+    pub fn total_count(&self) -> u64 {
+        self.count
+    }
+}
+
+impl RateLimiter {
+    /// Construct a new `RateLimiter` allowing one request per `period`.
+    pub fn new(period: Period) -> RateLimiter {
+        RateLimiter::with_burst(period, 1)
+    }
+    /// Construct a new `RateLimiter` allowing up to `burst` requests at once, refilled at
+    /// one request per `period`.
+    ///
+    /// Panics if `period` is not positive.
+    pub fn with_burst(period: Period, burst: u32) -> RateLimiter {
+        period.assert_positive();
+        RateLimiter {
+            period,
+            burst,
+            budget: burst as f64,
+            last_position: None,
+        }
+    }
+
+    /// Return the period in which one request is refilled.
+    pub fn period(&self) -> Period {
+        self.period
+    }
+    /// Return the maximum number of requests allowed at once.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+    /// Set the maximum number of requests allowed at once.
+    pub fn set_burst(&mut self, burst: u32) -> &mut RateLimiter {
+        self.burst = burst;
+        self.budget = self.budget.min(burst as f64);
+        self
+    }
+
+    /// Request permission during the frame `time`, returning true if it is allowed.
+    pub fn try_acquire<Tz: TimeZone>(&mut self, time: &GameTime<Tz>) -> bool {
+        self.refill(time);
+        if self.budget >= 1.0 {
+            self.budget -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+    /// Return the number of requests allowed during the frame `time`.
+    pub fn available<Tz: TimeZone>(&mut self, time: &GameTime<Tz>) -> u32 {
+        self.refill(time);
+        self.budget.floor() as u32
+    }
+
+    fn refill<Tz: TimeZone>(&mut self, time: &GameTime<Tz>) {
+        let position = self.period.position(time);
+        if let Some(last) = self.last_position {
+            let refilled = (position - last).max(0.0) / self.period.length();
+            self.budget = (self.budget + refilled).min(self.burst as f64);
+        }
+        self.last_position = Some(position);
+    }
+}
+
+impl Debounce {
+    /// Construct a new `Debounce` waiting for `period` without a trigger.
+    ///
+    /// Panics if `period` is not positive.
+    pub fn new(period: Period) -> Debounce {
+        period.assert_positive();
+        Debounce {
+            period,
+            last_trigger: None,
+        }
+    }
+
+    /// Return the quiet period required before firing.
+    pub fn period(&self) -> Period {
+        self.period
+    }
+    /// Record a trigger during the frame `time`, restarting the wait.
+    pub fn trigger<Tz: TimeZone>(&mut self, time: &GameTime<Tz>) {
+        self.last_trigger = Some(self.period.position(time));
+    }
+    /// Return true if a trigger is waiting to fire.
+    pub fn is_pending(&self) -> bool {
+        self.last_trigger.is_some()
+    }
+    /// Cancel any pending trigger.
+    pub fn cancel(&mut self) {
+        self.last_trigger = None;
+    }
+    /// Update for a new frame, returning true once a full period has passed since the
+    /// last trigger.
+    pub fn tick<Tz: TimeZone>(&mut self, time: &GameTime<Tz>) -> bool {
+        match self.last_trigger {
+            Some(trigger) if self.period.position(time) - trigger >= self.period.length() => {
+                self.last_trigger = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono;
+    use clock::GameClock;
+    use step;
+
+    #[test]
+    fn test_every() {
+        let mut clock = GameClock::new();
+        let step = step::VariableStep::new();
+        let start = clock.start_wall_time();
+        let mut game = Every::new(Period::GameTime(FloatDuration::seconds(1.0)));
+        let mut wall = Every::new(Period::WallTime(FloatDuration::seconds(1.0)));
+        let mut frames = Every::new(Period::Frames(3));
+
+        let time = clock.last_frame_time().clone();
+        assert_eq!(game.tick(&time), 0);
+        assert_eq!(wall.tick(&time), 0);
+        assert_eq!(frames.tick(&time), 0);
+
+        clock.set_clock_multiplier(10.0);
+        let mut counts = (0, 0, 0);
+        for i in 1..7 {
+            let frame_start = start + chrono::Duration::milliseconds(250 * i);
+            let time = clock.tick_with_wall_time(&step, frame_start);
+            counts.0 += game.tick(&time);
+            counts.1 += wall.tick(&time);
+            counts.2 += frames.tick(&time);
+        }
+        assert_eq!(game.elapsed(), 3);
+        assert_eq!(counts, (15, 1, 2));
+        assert_eq!(game.total_count(), 15);
+
+        game.set_period(Period::GameTime(FloatDuration::seconds(100.0)));
+        assert_eq!(game.elapsed(), 0);
+        assert_eq!(game.tick(&clock.last_frame_time().clone()), 0);
+        assert_eq!(game.total_count(), 0);
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut clock = GameClock::new();
+        let step = step::ConstantStep::new(FloatDuration::milliseconds(125.0));
+        let period = Period::GameTime(FloatDuration::seconds(0.5));
+        let mut limiter = RateLimiter::with_burst(period, 3);
+
+        let time = clock.tick(&step);
+        assert_eq!(limiter.available(&time), 3);
+        let allowed = (0..5).filter(|_| limiter.try_acquire(&time)).count();
+        assert_eq!(allowed, 3);
+
+        let mut allowed = 0;
+        for _ in 0..10 {
+            let time = clock.tick(&step);
+            if limiter.try_acquire(&time) {
+                allowed += 1;
+            }
+        }
+        assert_eq!(allowed, 2);
+
+        let mut throttle = RateLimiter::new(Period::Frames(2));
+        let allowed: Vec<bool> = (0..5)
+            .map(|_| {
+                let time = clock.tick(&step);
+                throttle.try_acquire(&time)
+            })
+            .collect();
+        assert_eq!(allowed, vec![true, false, true, false, true]);
+    }
+
+    #[test]
+    fn test_debounce() {
+        let mut clock = GameClock::new();
+        let step = step::ConstantStep::new(FloatDuration::seconds(1.0));
+        let mut debounce = Debounce::new(Period::GameTime(FloatDuration::seconds(2.0)));
+
+        let mut fired = Vec::new();
+        for i in 0..10 {
+            let time = clock.tick(&step);
+            if i < 4 {
+                debounce.trigger(&time);
+            }
+            if debounce.tick(&time) {
+                fired.push(i);
+            }
+        }
+        assert_eq!(fired, vec![5]);
+        assert!(!debounce.is_pending());
+    }
+
+    #[test]
+    #[should_panic(expected = "period must be positive")]
+    fn test_every_zero_period() {
+        Every::new(Period::Frames(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "period must be positive")]
+    fn test_every_set_zero_period() {
+        Every::new(Period::Frames(1)).set_period(Period::WallTime(FloatDuration::zero()));
+    }
+
+    #[test]
+    #[should_panic(expected = "period must be positive")]
+    fn test_rate_limiter_zero_period() {
+        RateLimiter::with_burst(Period::GameTime(FloatDuration::zero()), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "period must be positive")]
+    fn test_debounce_zero_period() {
+        Debounce::new(Period::Frames(0));
+    }
+}
@@ -1,9 +1,16 @@
 //! Implements the [`FrameRunner`](./runner/struct.FrameRunner.html) struct for managing frame simulations.
-use chrono;
+//!
+//! [`FixedUpdate`](./struct.FixedUpdate.html) runs a simulation at a fixed time step within
+//! frames of any length, and provides the alpha for interpolating rendered state.
+use chrono::{self, TimeZone};
+use float_duration::FloatDuration;
 
 use clock::{GameTime, GameClock};
 use framerate::counter::FrameCount;
-use step::TimeStep;
+use step::StatefulStep;
+
+/// The default maximum number of fixed updates run in a single frame by `FixedUpdate`.
+pub const DEFAULT_MAX_FIXED_STEPS: u32 = 8;
 
 /// A helper type for running frame simulations with a frame counter.
 ///
@@ -13,26 +20,44 @@ use step::TimeStep;
 /// a `tick` method like `GameClock`, and updates both the `GameClock` and
 /// `FrameCount` objects contained.
 #[derive(Debug)]
-pub struct FrameRunner<C: FrameCount> {
-    clock: GameClock,
+pub struct FrameRunner<C: FrameCount, Tz: TimeZone = chrono::Local> {
+    clock: GameClock<Tz>,
     counter: C,
 }
 
-impl<C> FrameRunner<C>
+/// An accumulator running a simulation at a fixed time step within frames of any length.
+///
+/// The elapsed game time of each frame is added to the accumulator, and
+/// [`tick`](#method.tick) returns the number of whole steps due. The time left over is
+/// reported by [`alpha`](#method.alpha) as a fraction of a step, for interpolating between
+/// the last two simulation states with
+/// [`Interpolated`](../interpolate/struct.Interpolated.html).
+///
+/// At most `max_steps` updates are run in one frame. Any time beyond that is dropped, so
+/// that a long stall does not leave the simulation ever further behind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedUpdate {
+    step: FloatDuration,
+    max_steps: u32,
+    accumulated: FloatDuration,
+}
+
+impl<C, Tz> FrameRunner<C, Tz>
 where
     C: FrameCount,
+    Tz: TimeZone,
 {
     /// Construct a new `FrameRunner` from a `GameClock` and a `FrameCount`.
-    pub fn new(clock: GameClock, counter: C) -> FrameRunner<C> {
+    pub fn new(clock: GameClock<Tz>, counter: C) -> FrameRunner<C, Tz> {
         FrameRunner { clock, counter }
     }
 
     /// Get a reference to the contained `GameClock`.
-    pub fn clock(&self) -> &GameClock {
+    pub fn clock(&self) -> &GameClock<Tz> {
         &self.clock
     }
     /// Get a mutable reference to the contained `GameClock`.
-    pub fn clock_mut(&mut self) -> &mut GameClock {
+    pub fn clock_mut(&mut self) -> &mut GameClock<Tz> {
         &mut self.clock
     }
     /// Get a reference to the contained `FrameCount`.
@@ -47,7 +72,7 @@ where
     ///
     /// The `GameTime` for the new frame is returned, with the same properties as that
     /// returned from [`GameClock::tick`](../clock/struct.GameClock.html#method.tick).
-    pub fn tick<T: TimeStep>(&mut self, time_step: &T) -> GameTime {
+    pub fn tick<S: StatefulStep>(&mut self, time_step: S) -> GameTime<Tz> {
         let time = self.clock.tick(time_step);
         self.counter.tick(&time);
         time
@@ -57,11 +82,11 @@ where
     ///
     /// This function is like `tick` but allows for the start time for the
     /// frame to be specified.
-    pub fn tick_with_wall_time<T: TimeStep>(
+    pub fn tick_with_wall_time<S: StatefulStep>(
         &mut self,
-        time_step: &T,
-        frame_start: chrono::DateTime<chrono::Local>,
-    ) -> GameTime {
+        time_step: S,
+        frame_start: chrono::DateTime<Tz>,
+    ) -> GameTime<Tz> {
         let time = self.clock.tick_with_wall_time(time_step, frame_start);
         self.counter.tick(&time);
         time
@@ -73,10 +98,10 @@ where
     /// and will call
     /// [`GameClock::sleep_remaining`](../clock/struct.GameClock.html#method.sleep_remaining)
     /// after the closure has ended.
-    pub fn do_frame<T, F>(&mut self, time_step: &T, frame_fn: F)
+    pub fn do_frame<S, F>(&mut self, time_step: S, frame_fn: F)
     where
-        T: TimeStep,
-        F: FnOnce(GameTime),
+        S: StatefulStep,
+        F: FnOnce(GameTime<Tz>),
     {
         let time = self.tick(time_step);
         frame_fn(time);
@@ -84,6 +109,77 @@ where
     }
 }
 
+impl FixedUpdate {
+    /// Construct a new `FixedUpdate` running steps of `step` game time.
+    ///
+    /// Panics if `step` is not positive.
+    pub fn new(step: FloatDuration) -> FixedUpdate {
+        assert_positive_step(step);
+        FixedUpdate {
+            step,
+            max_steps: DEFAULT_MAX_FIXED_STEPS,
+            accumulated: FloatDuration::zero(),
+        }
+    }
+
+    /// Return the game time of each fixed step.
+    pub fn step(&self) -> FloatDuration {
+        self.step
+    }
+    /// Set the game time of each fixed step.
+    ///
+    /// Panics if `step` is not positive.
+    pub fn set_step(&mut self, step: FloatDuration) -> &mut FixedUpdate {
+        assert_positive_step(step);
+        self.step = step;
+        self
+    }
+    /// Return the maximum number of steps run in one frame.
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+    /// Set the maximum number of steps run in one frame, which is at least `1`.
+    pub fn set_max_steps(&mut self, max_steps: u32) -> &mut FixedUpdate {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+    /// Return the game time accumulated towards the next step.
+    pub fn accumulated(&self) -> FloatDuration {
+        self.accumulated
+    }
+    /// Return the fraction of the next step that has accumulated, in `[0.0, 1.0)`.
+    ///
+    /// This is the alpha between the previous and current simulation state for rendering
+    /// the frame.
+    pub fn alpha(&self) -> f64 {
+        self.accumulated / self.step
+    }
+    /// Discard the accumulated time.
+    pub fn reset(&mut self) {
+        self.accumulated = FloatDuration::zero();
+    }
+
+    /// Add the elapsed game time of the frame `time`, returning the number of fixed steps
+    /// to run for it.
+    ///
+    /// Game time running backwards does not undo steps already run.
+    pub fn tick<Tz: TimeZone>(&mut self, time: &GameTime<Tz>) -> u32 {
+        let accumulated = self.accumulated + time.elapsed_game_time();
+        self.accumulated = accumulated.max(FloatDuration::zero());
+        let due = (self.accumulated / self.step).floor();
+        self.accumulated -= self.step * due;
+        if due > self.max_steps as f64 {
+            self.max_steps
+        } else {
+            due as u32
+        }
+    }
+}
+
+fn assert_positive_step(step: FloatDuration) {
+    assert!(step.as_seconds() > 0.0, "fixed update step must be positive, got {:?}", step);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,4 +210,38 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_fixed_update() {
+        let counter =
+            counter::FrameCounter::new(20.0, sample::RunningAverageSampler::with_max_samples(20));
+        let mut runner = FrameRunner::new(GameClock::new(), counter);
+        let frame = step::ConstantStep::new(FloatDuration::seconds(0.625));
+        let mut fixed = FixedUpdate::new(FloatDuration::seconds(0.25));
+
+        assert_eq!(fixed.tick(&runner.tick(&frame)), 2);
+        assert_eq!(fixed.alpha(), 0.5);
+        assert_eq!(fixed.tick(&runner.tick(&frame)), 3);
+        assert_eq!(fixed.alpha(), 0.0);
+
+        // Paused frames run no steps.
+        runner.clock_mut().set_clock_multiplier(0.0);
+        assert_eq!(fixed.tick(&runner.tick(&frame)), 0);
+
+        // A stall runs at most `max_steps` and drops the rest.
+        runner.clock_mut().set_clock_multiplier(1.0);
+        let stall = step::ConstantStep::new(FloatDuration::seconds(10.125));
+        assert_eq!(fixed.tick(&runner.tick(&stall)), DEFAULT_MAX_FIXED_STEPS);
+        assert_eq!(fixed.alpha(), 0.5);
+
+        fixed.set_max_steps(0).reset();
+        assert_eq!(fixed.max_steps(), 1);
+        assert_eq!(fixed.accumulated(), FloatDuration::zero());
+    }
+
+    #[test]
+    #[should_panic(expected = "fixed update step must be positive")]
+    fn test_fixed_update_zero_step() {
+        FixedUpdate::new(FloatDuration::zero());
+    }
 }
@@ -0,0 +1,270 @@
+//! Sharing of the current frame time with other threads.
+//!
+//! The thread running the [`GameClock`](../clock/struct.GameClock.html) publishes each new
+//! [`GameTime`](../clock/struct.GameTime.html) through a
+//! [`ClockPublisher`](./struct.ClockPublisher.html), and any number of
+//! [`SharedClock`](./struct.SharedClock.html) handles can read it from other threads.
+//!
+//! Reads never block or lock. The time is stored in a sequence lock: a reader retries if it
+//! overlaps a publish, which is only ever as long as copying a few values.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::thread;
+//! use game_time::{GameClock, FloatDuration};
+//! use game_time::shared::ClockPublisher;
+//! use game_time::step;
+//!
+//! let mut clock = GameClock::new();
+//! let mut publisher = ClockPublisher::new(clock.last_frame_time());
+//! let shared = publisher.shared();
+//!
+//! let worker = thread::spawn(move || {
+//!     let time = shared.latest();
+//!     assert!(time.frame_number() <= 10);
+//! });
+//!
+//! for _ in 0..10 {
+//!     let time = clock.tick(&step::ConstantStep::new(FloatDuration::milliseconds(16.0)));
+//!     publisher.publish(&time);
+//! }
+//! worker.join().unwrap();
+//! ```
+use std::hint;
+use std::sync::Arc;
+use std::sync::atomic::{self, AtomicU64, AtomicUsize, Ordering};
+
+use chrono::{self, TimeZone};
+use float_duration::{FloatDuration, TimePoint};
+
+use clock::{self, GameTime};
+
+/// The publishing side of a shared clock.
+///
+/// There is exactly one `ClockPublisher` for each set of `SharedClock` handles, which is
+/// what allows readers to proceed without locking.
+#[derive(Debug)]
+pub struct ClockPublisher<Tz: TimeZone = chrono::Local> {
+    inner: Arc<SharedTime<Tz>>,
+}
+
+/// A thread-safe handle for reading the most recently published frame time.
+///
+/// `SharedClock` is cheap to clone, and each clone reads the same published time.
+#[derive(Debug, Clone)]
+pub struct SharedClock<Tz: TimeZone = chrono::Local> {
+    inner: Arc<SharedTime<Tz>>,
+}
+
+#[derive(Debug)]
+struct SharedTime<Tz: TimeZone> {
+    sequence: AtomicUsize,
+    // Frame start times are stored as nanoseconds from this fixed origin, which keeps the
+    // time zone offset out of the atomics.
+    origin: chrono::DateTime<Tz>,
+    frame_start_nanos: AtomicU64,
+    total_wall_time: AtomicU64,
+    total_game_time: AtomicU64,
+    elapsed_game_time: AtomicU64,
+    elapsed_wall_time: AtomicU64,
+    frame_number: AtomicU64,
+    clock_multiplier: AtomicU64,
+}
+
+impl<Tz: TimeZone> ClockPublisher<Tz> {
+    /// Construct a new `ClockPublisher`, initially publishing `time`.
+    pub fn new(time: &GameTime<Tz>) -> ClockPublisher<Tz> {
+        let inner = SharedTime {
+            sequence: AtomicUsize::new(0),
+            origin: time.frame_start_time(),
+            frame_start_nanos: AtomicU64::new(0),
+            total_wall_time: AtomicU64::new(0),
+            total_game_time: AtomicU64::new(0),
+            elapsed_game_time: AtomicU64::new(0),
+            elapsed_wall_time: AtomicU64::new(0),
+            frame_number: AtomicU64::new(0),
+            clock_multiplier: AtomicU64::new(0),
+        };
+        inner.store(time);
+
+        ClockPublisher {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Publish a new frame time, making it visible to all `SharedClock` handles.
+    pub fn publish(&mut self, time: &GameTime<Tz>) {
+        let seq = self.inner.sequence.load(Ordering::Relaxed);
+        self.inner.sequence.store(seq.wrapping_add(1), Ordering::Relaxed);
+        atomic::fence(Ordering::Release);
+
+        self.inner.store(time);
+
+        self.inner
+            .sequence
+            .store(seq.wrapping_add(2), Ordering::Release);
+    }
+    /// Return a new handle for reading the published time.
+    pub fn shared(&self) -> SharedClock<Tz> {
+        SharedClock {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<Tz: TimeZone> SharedClock<Tz> {
+    /// Return the most recently published frame time.
+    pub fn latest(&self) -> GameTime<Tz> {
+        loop {
+            let seq = self.inner.sequence.load(Ordering::Acquire);
+            if seq % 2 == 1 {
+                hint::spin_loop();
+                continue;
+            }
+
+            let time = self.inner.load();
+
+            atomic::fence(Ordering::Acquire);
+            if self.inner.sequence.load(Ordering::Relaxed) == seq {
+                return time;
+            }
+        }
+    }
+    /// Return the number of the most recently published frame.
+    ///
+    /// This is cheaper than `latest` when only the frame number is needed.
+    pub fn frame_number(&self) -> u64 {
+        self.inner.frame_number.load(Ordering::Acquire)
+    }
+    /// Estimate the current game time.
+    ///
+    /// See [`estimate_game_time_at`](#method.estimate_game_time_at).
+    pub fn estimate_game_time(&self) -> FloatDuration {
+        self.estimate_game_time_at(clock::now_in(&self.inner.origin.timezone()))
+    }
+    /// Estimate the game time at the wall time `now`.
+    ///
+    /// The game time is extrapolated from the start of the latest frame using that frame's
+    /// clock multiplier, as if game time were coupled to wall time. With a fixed or constant
+    /// time step, this estimate can differ from the game time of the next frame.
+    pub fn estimate_game_time_at(&self, now: chrono::DateTime<Tz>) -> FloatDuration {
+        let time = self.latest();
+        let since_frame_start = now.float_duration_since(time.frame_start_time())
+            .unwrap();
+        time.total_game_time() + since_frame_start * time.clock_multiplier()
+    }
+}
+
+impl<Tz: TimeZone> SharedTime<Tz> {
+    fn store(&self, time: &GameTime<Tz>) {
+        let frame_start = time.frame_start_time()
+            .signed_duration_since(self.origin.clone())
+            .num_nanoseconds()
+            .unwrap_or(i64::MAX);
+
+        self.frame_start_nanos
+            .store(frame_start as u64, Ordering::Relaxed);
+        store_seconds(&self.total_wall_time, time.total_wall_time());
+        store_seconds(&self.total_game_time, time.total_game_time());
+        store_seconds(&self.elapsed_game_time, time.elapsed_game_time());
+        store_seconds(&self.elapsed_wall_time, time.elapsed_wall_time());
+        self.frame_number
+            .store(time.frame_number(), Ordering::Relaxed);
+        self.clock_multiplier
+            .store(time.clock_multiplier().to_bits(), Ordering::Relaxed);
+    }
+
+    fn load(&self) -> GameTime<Tz> {
+        let frame_start_nanos = self.frame_start_nanos.load(Ordering::Relaxed) as i64;
+        GameTime::from_parts(
+            self.origin.clone() + chrono::Duration::nanoseconds(frame_start_nanos),
+            load_seconds(&self.total_wall_time),
+            load_seconds(&self.total_game_time),
+            load_seconds(&self.elapsed_game_time),
+            load_seconds(&self.elapsed_wall_time),
+            self.frame_number.load(Ordering::Relaxed),
+            f64::from_bits(self.clock_multiplier.load(Ordering::Relaxed)),
+        )
+    }
+}
+
+fn store_seconds(atomic: &AtomicU64, duration: FloatDuration) {
+    atomic.store(duration.as_seconds().to_bits(), Ordering::Relaxed);
+}
+
+fn load_seconds(atomic: &AtomicU64) -> FloatDuration {
+    FloatDuration::seconds(f64::from_bits(atomic.load(Ordering::Relaxed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    use clock::{GameClock, GameClockBuilder};
+    use step;
+
+    #[test]
+    fn test_publish() {
+        let mut clock = GameClockBuilder::new().clock_multiplier(2.0).build();
+        let mut publisher = ClockPublisher::new(clock.last_frame_time());
+        let shared = publisher.shared();
+        assert_eq!(shared.frame_number(), 0);
+
+        let start_time = clock.start_wall_time();
+        let frame_time = start_time + chrono::Duration::milliseconds(100);
+        let time = clock.tick_with_wall_time(&step::VariableStep::new(), frame_time);
+        publisher.publish(&time);
+
+        let latest = shared.clone().latest();
+        assert_eq!(latest.frame_number(), 1);
+        assert_eq!(latest.frame_start_time(), frame_time);
+        assert_eq!(latest.total_wall_time(), time.total_wall_time());
+        assert_eq!(latest.total_game_time(), time.total_game_time());
+        assert_eq!(latest.elapsed_wall_time(), time.elapsed_wall_time());
+        assert_eq!(latest.elapsed_game_time(), time.elapsed_game_time());
+        assert_eq!(latest.clock_multiplier(), 2.0);
+
+        let later = frame_time + chrono::Duration::milliseconds(50);
+        assert!(relative_eq!(
+            shared.estimate_game_time_at(later).as_seconds(),
+            0.3,
+            epsilon = 1e-9
+        ));
+    }
+
+    #[test]
+    fn test_concurrent_reads() {
+        let mut clock = GameClock::new();
+        let mut publisher = ClockPublisher::new(clock.last_frame_time());
+        let step = step::ConstantStep::new(FloatDuration::seconds(0.5));
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let shared = publisher.shared();
+                thread::spawn(move || {
+                    let mut last_frame = 0;
+                    while last_frame < 10_000 {
+                        let time = shared.latest();
+                        // A torn read would mix values from different frames.
+                        assert_eq!(
+                            time.total_game_time().as_seconds(),
+                            time.frame_number() as f64 * 0.5
+                        );
+                        assert!(time.frame_number() >= last_frame);
+                        last_frame = time.frame_number();
+                    }
+                })
+            })
+            .collect();
+
+        for _ in 0..10_000 {
+            let time = clock.tick(&step);
+            publisher.publish(&time);
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//! Frame rate independent smoothing and damping.
+//!
+//! Moving a value a fixed fraction toward a target every frame, as in `lerp(a, b, 0.1)`,
+//! converges faster at higher frame rates. The helpers in this module instead use the
+//! elapsed game time of each frame, so the result after a given amount of game time is the
+//! same whatever the frame rate. They also stop while the game is paused.
+//!
+//! - [`exp_decay`](./fn.exp_decay.html) and [`half_life_decay`](./fn.half_life_decay.html)
+//!   move a value exponentially toward a target.
+//! - [`fixed_rate_lerp`](./fn.fixed_rate_lerp.html) reproduces a per-frame `lerp` tuned at a
+//!   reference frame rate.
+//! - [`SmoothDamp`](./struct.SmoothDamp.html) follows a target with a critically damped
+//!   spring, which also smooths changes in velocity.
+//!
+//! The helpers work with any [`Lerp`](../interpolate/trait.Lerp.html) type, and
+//! `SmoothDamp` with any [`Vector`](./trait.Vector.html), including scalars and fixed-size
+//! arrays.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use game_time::{GameClock, FloatDuration};
+//! use game_time::smooth;
+//! use game_time::step;
+//!
+//! let mut clock = GameClock::new();
+//! let step = step::ConstantStep::new(FloatDuration::milliseconds(100.0));
+//! let mut camera = [0.0f64, 0.0];
+//! let target = [8.0, 4.0];
+//!
+//! for _ in 0..10 {
+//!     let time = clock.tick(&step);
+//!     camera = smooth::half_life_decay(&camera, &target, FloatDuration::seconds(0.5), &time);
+//! }
+//! assert!((camera[0] - 6.0).abs() < 1e-9);
+//! assert!((camera[1] - 3.0).abs() < 1e-9);
+//! ```
+use std::array;
+
+use chrono::TimeZone;
+use float_duration::FloatDuration;
+
+use clock::GameTime;
+use interpolate::Lerp;
+
+/// Arithmetic needed by `SmoothDamp`.
+pub trait Vector: Sized {
+    /// The zero value.
+    fn zero() -> Self;
+    /// Return the sum of `self` and `other`.
+    fn add(&self, other: &Self) -> Self;
+    /// Return `self` minus `other`.
+    fn sub(&self, other: &Self) -> Self;
+    /// Return `self` multiplied by `factor`.
+    fn scale(&self, factor: f64) -> Self;
+}
+
+/// A value following a target with a critically damped spring.
+///
+/// The spring is integrated exactly, so the motion does not depend on the frame rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmoothDamp<T> {
+    value: T,
+    velocity: T,
+    smooth_time: FloatDuration,
+}
+
+impl Vector for f32 {
+    fn zero() -> f32 {
+        0.0
+    }
+    fn add(&self, other: &f32) -> f32 {
+        self + other
+    }
+    fn sub(&self, other: &f32) -> f32 {
+        self - other
+    }
+    fn scale(&self, factor: f64) -> f32 {
+        self * factor as f32
+    }
+}
+
+impl Vector for f64 {
+    fn zero() -> f64 {
+        0.0
+    }
+    fn add(&self, other: &f64) -> f64 {
+        self + other
+    }
+    fn sub(&self, other: &f64) -> f64 {
+        self - other
+    }
+    fn scale(&self, factor: f64) -> f64 {
+        self * factor
+    }
+}
+
+impl<T: Vector, const N: usize> Vector for [T; N] {
+    fn zero() -> [T; N] {
+        array::from_fn(|_| T::zero())
+    }
+    fn add(&self, other: &[T; N]) -> [T; N] {
+        array::from_fn(|i| self[i].add(&other[i]))
+    }
+    fn sub(&self, other: &[T; N]) -> [T; N] {
+        array::from_fn(|i| self[i].sub(&other[i]))
+    }
+    fn scale(&self, factor: f64) -> [T; N] {
+        array::from_fn(|i| self[i].scale(factor))
+    }
+}
+
+/// Move `current` toward `target` by exponential decay at `rate` per second of game time.
+///
+/// The distance to the target is multiplied by `e^(-rate * dt)` each frame.
+pub fn exp_decay<T, Tz>(current: &T, target: &T, rate: f64, time: &GameTime<Tz>) -> T
+where
+    T: Lerp,
+    Tz: TimeZone,
+{
+    let dt = time.elapsed_game_time().as_seconds();
+    current.lerp(target, 1.0 - (-rate * dt).exp())
+}
+
+/// Move `current` toward `target`, halving the distance every `half_life` of game time.
+pub fn half_life_decay<T, Tz>(
+    current: &T,
+    target: &T,
+    half_life: FloatDuration,
+    time: &GameTime<Tz>,
+) -> T
+where
+    T: Lerp,
+    Tz: TimeZone,
+{
+    let dt = time.elapsed_game_time() / half_life;
+    current.lerp(target, 1.0 - 2f64.powf(-dt))
+}
+
+/// Move `current` toward `target` as `current.lerp(target, fraction)` would each frame at
+/// a frame time of `reference_frame_time`, whatever the actual frame time.
+///
+/// This converts a per-frame lerp tuned at a fixed frame rate into a frame rate independent
+/// one.
+pub fn fixed_rate_lerp<T, Tz>(
+    current: &T,
+    target: &T,
+    fraction: f64,
+    reference_frame_time: FloatDuration,
+    time: &GameTime<Tz>,
+) -> T
+where
+    T: Lerp,
+    Tz: TimeZone,
+{
+    let frames = time.elapsed_game_time() / reference_frame_time;
+    current.lerp(target, 1.0 - (1.0 - fraction).powf(frames))
+}
+
+impl<T: Vector> SmoothDamp<T> {
+    /// Construct a new `SmoothDamp` at rest at `initial`.
+    ///
+    /// `smooth_time` is roughly the time taken to reach a stationary target.
+    pub fn new(initial: T, smooth_time: FloatDuration) -> SmoothDamp<T> {
+        SmoothDamp {
+            value: initial,
+            velocity: T::zero(),
+            smooth_time,
+        }
+    }
+
+    /// Return the current value.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+    /// Return the current velocity, per second of game time.
+    pub fn velocity(&self) -> &T {
+        &self.velocity
+    }
+    /// Return the smoothing time.
+    pub fn smooth_time(&self) -> FloatDuration {
+        self.smooth_time
+    }
+    /// Set the smoothing time.
+    pub fn set_smooth_time(&mut self, smooth_time: FloatDuration) -> &mut SmoothDamp<T> {
+        self.smooth_time = smooth_time;
+        self
+    }
+    /// Move to `value` and come to rest there.
+    pub fn reset(&mut self, value: T) {
+        self.value = value;
+        self.velocity = T::zero();
+    }
+
+    /// Advance the spring toward `target` by the elapsed game time of `time`, returning the
+    /// new value.
+    pub fn update<Tz: TimeZone>(&mut self, target: &T, time: &GameTime<Tz>) -> &T {
+        let dt = time.elapsed_game_time().as_seconds();
+        let omega = 2.0 / self.smooth_time.as_seconds();
+        let decay = (-omega * dt).exp();
+
+        let offset = self.value.sub(target);
+        let impulse = self.velocity.add(&offset.scale(omega));
+        self.value = target.add(&offset.add(&impulse.scale(dt)).scale(decay));
+        self.velocity = self.velocity.sub(&impulse.scale(omega * dt)).scale(decay);
+        &self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::GameClock;
+    use step;
+
+    /// Run `f` for one second of game time at `fps` frames per second.
+    fn run_frames<F>(fps: f64, mut f: F)
+    where
+        F: FnMut(&GameTime),
+    {
+        let mut clock = GameClock::new();
+        let step = step::ConstantStep::new(FloatDuration::seconds(1.0 / fps));
+        for _ in 0..fps as u32 {
+            f(&clock.tick(&step));
+        }
+    }
+
+    #[test]
+    fn test_decay_independent_of_frame_rate() {
+        let results: Vec<(f64, f64, f64)> = [30.0, 144.0]
+            .iter()
+            .map(|&fps| {
+                let (mut exp, mut half, mut lerp) = (0.0, 0.0, 0.0);
+                let reference = FloatDuration::seconds(1.0 / 60.0);
+                run_frames(fps, |time| {
+                    exp = exp_decay(&exp, &1.0, 3.0, time);
+                    half = half_life_decay(&half, &1.0, FloatDuration::seconds(0.25), time);
+                    lerp = fixed_rate_lerp(&lerp, &1.0, 0.1, reference, time);
+                });
+                (exp, half, lerp)
+            })
+            .collect();
+
+        assert!(relative_eq!(results[0].0, 1.0 - (-3.0f64).exp(), epsilon = 1e-9));
+        assert!(relative_eq!(results[0].1, 1.0 - 1.0 / 16.0, epsilon = 1e-9));
+        assert!(relative_eq!(results[0].2, 1.0 - 0.9f64.powi(60), epsilon = 1e-9));
+        assert!(relative_eq!(results[0].0, results[1].0, epsilon = 1e-9));
+        assert!(relative_eq!(results[0].1, results[1].1, epsilon = 1e-9));
+        assert!(relative_eq!(results[0].2, results[1].2, epsilon = 1e-9));
+    }
+
+    #[test]
+    fn test_smooth_damp() {
+        let results: Vec<SmoothDamp<[f64; 2]>> = [30.0, 144.0]
+            .iter()
+            .map(|&fps| {
+                let mut damp = SmoothDamp::new([0.0, 10.0], FloatDuration::seconds(0.3));
+                run_frames(fps, |time| {
+                    damp.update(&[5.0, 0.0], time);
+                });
+                damp
+            })
+            .collect();
+
+        for axis in 0..2 {
+            assert!(relative_eq!(
+                results[0].value()[axis],
+                results[1].value()[axis],
+                epsilon = 1e-9
+            ));
+            assert!(relative_eq!(
+                results[0].velocity()[axis],
+                results[1].velocity()[axis],
+                epsilon = 1e-9
+            ));
+        }
+        // A critically damped spring approaches without overshooting.
+        assert!(results[0].value()[0] < 5.0 && results[0].value()[0] > 4.9);
+        assert!(results[0].value()[1] > 0.0 && results[0].value()[1] < 0.2);
+
+        let mut damp = results[0].clone();
+        damp.reset([1.0, 1.0]);
+        assert_eq!(damp.velocity(), &[0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_paused() {
+        let mut clock = GameClock::new();
+        clock.set_clock_multiplier(0.0);
+        let time = clock.tick(&step::ConstantStep::new(FloatDuration::seconds(1.0)));
+        assert_eq!(exp_decay(&2.0f32, &4.0, 10.0, &time), 2.0);
+        let mut damp = SmoothDamp::new(1.0f32, FloatDuration::seconds(0.1));
+        assert_eq!(*damp.update(&3.0, &time), 1.0);
+    }
+}
@@ -0,0 +1,239 @@
+//! Spreading periodic work evenly across frames.
+//!
+//! [`Stagger`](./struct.Stagger.html) decides whether the update for a key runs on a given
+//! frame. A key with an update interval of `n` runs on every `n`th frame, offset by the key
+//! so that about `1 / n` of all keys run each frame. Each update reports the game time
+//! accumulated since the key last ran, so a delayed update can integrate over the frames it
+//! skipped.
+//!
+//! Whether a key runs depends only on the frame number, its offset and its interval, not on
+//! when it last ran. Changing intervals at runtime therefore never causes a burst of updates
+//! on a single frame.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use game_time::{GameClock, FloatDuration};
+//! use game_time::stagger::Stagger;
+//! use game_time::step;
+//!
+//! let mut clock = GameClock::new();
+//! let mut stagger = Stagger::new();
+//! let step = step::ConstantStep::new(FloatDuration::milliseconds(10.0));
+//!
+//! let mut ages = vec![FloatDuration::zero(); 100];
+//! for _ in 0..40 {
+//!     let time = clock.tick(&step);
+//!     let mut updated = 0;
+//!     for entity in 0..100 {
+//!         if let Some(dt) = stagger.update_with_offset(entity, entity as u64, 4, &time) {
+//!             ages[entity] += dt;
+//!             updated += 1;
+//!         }
+//!     }
+//!     assert_eq!(updated, 25);
+//! }
+//! assert!(ages.iter().all(|&age| age > FloatDuration::milliseconds(360.0)));
+//! ```
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use chrono::TimeZone;
+use float_duration::FloatDuration;
+
+use clock::GameTime;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LastUpdate {
+    game_time: FloatDuration,
+    frame_number: u64,
+}
+
+/// A scheduler running keyed updates on a subset of frames.
+///
+/// A key seen for the first time is treated as if it last ran at the start of the previous
+/// frame, so its first update covers one frame of game time.
+#[derive(Debug, Clone)]
+pub struct Stagger<K: Eq + Hash> {
+    last_updates: HashMap<K, LastUpdate>,
+}
+
+impl<K: Eq + Hash> Stagger<K> {
+    /// Construct a new, empty `Stagger`.
+    pub fn new() -> Stagger<K> {
+        Stagger {
+            last_updates: HashMap::new(),
+        }
+    }
+
+    /// Return the frame offset of `key`, derived from its hash.
+    pub fn offset(key: &K) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Decide whether the update for `key` runs during the frame `time`, with an interval
+    /// of `interval` frames.
+    ///
+    /// Returns the game time since `key` last ran if it runs this frame, and `None`
+    /// otherwise. The offset of `key` is derived from its hash, which spreads keys across
+    /// frames statistically.
+    pub fn update<Tz: TimeZone>(
+        &mut self,
+        key: K,
+        interval: u64,
+        time: &GameTime<Tz>,
+    ) -> Option<FloatDuration> {
+        let offset = Stagger::offset(&key);
+        self.update_with_offset(key, offset, interval, time)
+    }
+    /// Decide whether the update for `key` runs during the frame `time`, with an explicit
+    /// frame offset.
+    ///
+    /// Sequential offsets, such as entity ids, spread keys exactly evenly across frames.
+    /// A key runs at most once per frame.
+    pub fn update_with_offset<Tz: TimeZone>(
+        &mut self,
+        key: K,
+        offset: u64,
+        interval: u64,
+        time: &GameTime<Tz>,
+    ) -> Option<FloatDuration> {
+        let now = LastUpdate {
+            game_time: time.total_game_time(),
+            frame_number: time.frame_number(),
+        };
+        let due = is_due(offset, interval, now.frame_number);
+        let last = self.last_updates.entry(key).or_insert(LastUpdate {
+            game_time: now.game_time - time.elapsed_game_time(),
+            frame_number: now.frame_number.saturating_sub(1),
+        });
+
+        if !due || last.frame_number == now.frame_number {
+            return None;
+        }
+        let accumulated = now.game_time - last.game_time;
+        *last = now;
+        Some(accumulated)
+    }
+
+    /// Return the game time at which `key` last ran, if it has been seen.
+    pub fn last_update(&self, key: &K) -> Option<FloatDuration> {
+        self.last_updates.get(key).map(|last| last.game_time)
+    }
+    /// Stop tracking `key`, returning true if it was tracked.
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.last_updates.remove(key).is_some()
+    }
+    /// Return the number of keys tracked.
+    pub fn len(&self) -> usize {
+        self.last_updates.len()
+    }
+    /// Return true if no keys are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.last_updates.is_empty()
+    }
+    /// Stop tracking all keys.
+    pub fn clear(&mut self) {
+        self.last_updates.clear();
+    }
+}
+
+impl<K: Eq + Hash> Default for Stagger<K> {
+    fn default() -> Stagger<K> {
+        Stagger::new()
+    }
+}
+
+/// Return true if a key with `offset` runs on frame `frame_number` with `interval`.
+///
+/// An interval of `0` or `1` runs on every frame.
+pub fn is_due(offset: u64, interval: u64, frame_number: u64) -> bool {
+    interval <= 1 || frame_number.wrapping_add(offset % interval) % interval == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::GameClock;
+    use step;
+
+    #[test]
+    fn test_even_spread() {
+        let mut clock = GameClock::new();
+        let step = step::ConstantStep::new(FloatDuration::milliseconds(10.0));
+        let mut stagger = Stagger::new();
+
+        let mut totals = vec![FloatDuration::zero(); 30];
+        for _ in 0..30 {
+            let time = clock.tick(&step);
+            let mut updated = 0;
+            for (entity, total) in totals.iter_mut().enumerate() {
+                if let Some(dt) = stagger.update_with_offset(entity, entity as u64, 3, &time) {
+                    *total += dt;
+                    updated += 1;
+                }
+            }
+            assert_eq!(updated, 10);
+        }
+
+        // Every entity integrates over all the game time up to its last update.
+        for (entity, total) in totals.iter().enumerate() {
+            let last = stagger.last_update(&entity).unwrap();
+            assert!(relative_eq!(
+                total.as_seconds(),
+                last.as_seconds(),
+                epsilon = 1e-9
+            ));
+            assert!(last >= FloatDuration::milliseconds(280.0));
+        }
+    }
+
+    #[test]
+    fn test_interval_change() {
+        let mut clock = GameClock::new();
+        let step = step::ConstantStep::new(FloatDuration::milliseconds(10.0));
+        let mut stagger = Stagger::new();
+
+        for frame in 0..40 {
+            let interval = if frame < 20 { 8 } else { 2 };
+            let time = clock.tick(&step);
+            let updated = (0..80)
+                .filter(|&entity| {
+                    stagger
+                        .update_with_offset(entity, entity as u64, interval, &time)
+                        .is_some()
+                })
+                .count();
+            assert_eq!(updated, 80 / interval as usize);
+        }
+    }
+
+    #[test]
+    fn test_hashed_offset() {
+        let mut clock = GameClock::new();
+        let step = step::ConstantStep::new(FloatDuration::milliseconds(10.0));
+        let mut stagger = Stagger::new();
+        let time = clock.tick(&step);
+
+        assert_eq!(stagger.update("player", 1, &time), Some(step_duration()));
+        assert_eq!(stagger.update("player", 1, &time), None);
+        assert_eq!(stagger.len(), 1);
+        assert!(stagger.remove(&"player"));
+        assert!(stagger.is_empty());
+
+        let runs = (0..4)
+            .filter(|_| {
+                let time = clock.tick(&step);
+                stagger.update("tree", 4, &time).is_some()
+            })
+            .count();
+        assert_eq!(runs, 1);
+    }
+
+    fn step_duration() -> FloatDuration {
+        FloatDuration::milliseconds(10.0)
+    }
+}
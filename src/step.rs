@@ -1,6 +1,58 @@
 //! Types for computing game time from wall time.
+//!
+//! A [`TimeStep`](./trait.TimeStep.html) computes each step from the elapsed wall time alone.
+//! Steps that need to keep state between frames, such as smoothing or error feedback,
+//! implement [`StatefulStep`](./trait.StatefulStep.html) instead. `GameClock::tick` accepts
+//! any `StatefulStep`, which includes references to `TimeStep` types:
+//!
+//! - `&step` for a `TimeStep`, as before.
+//! - `&mut step` for a `StatefulStep` kept across frames.
+//!
+//! Time steps can be composed with the combinators in
+//! [`TimeStepExt`](./trait.TimeStepExt.html), and stateful steps with the same combinators
+//! in [`StatefulStepExt`](./trait.StatefulStepExt.html). [`FnStep`](./struct.FnStep.html)
+//! turns a closure into a time step.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use game_time::{FloatDuration, TimeStep};
+//! use game_time::step::{ConstantStep, TimeStepExt, VariableStep};
+//!
+//! // Follow wall time at half speed, but never step more than 50ms or less than 5ms.
+//! let ms = FloatDuration::milliseconds;
+//! let step = VariableStep::new().scaled(0.5).clamped(ms(5.0), ms(50.0));
+//! assert_eq!(step.time_step(&ms(40.0)), ms(20.0));
+//! assert_eq!(step.time_step(&FloatDuration::seconds(2.0)), ms(50.0));
+//!
+//! let step = step.max_of(ConstantStep::new(ms(10.0)));
+//! assert_eq!(step.time_step(&ms(4.0)), ms(10.0));
+//! ```
+//!
+//! Stateful steps are combined the same way, taking plain time steps by reference:
+//!
+//! ```rust
+//! use game_time::{GameClock, FloatDuration};
+//! use game_time::step::{ConstantStep, SmoothedStep, StatefulStepExt};
+//!
+//! let ms = FloatDuration::milliseconds;
+//! let mut clock = GameClock::new();
+//! let min_step = ConstantStep::new(ms(5.0));
+//! let mut step = SmoothedStep::new().clamped(ms(0.0), ms(50.0)).max_of(&min_step);
+//! let time = clock.tick(&mut step);
+//! assert!(time.elapsed_game_time() >= ms(5.0));
+//! ```
+use std::collections::VecDeque;
+
+use chrono::TimeZone;
 use float_duration::FloatDuration;
+
+use clock::GameTime;
 use framerate::FrameCount;
+use runner::FrameRunner;
+
+/// The default number of frames averaged by `SmoothedStep`.
+pub const DEFAULT_SMOOTHING_SAMPLES: u32 = 8;
 
 /// Compute elapsed game time for a frame.
 pub trait TimeStep {
@@ -8,6 +60,139 @@ pub trait TimeStep {
     fn time_step(&self, wall_time: &FloatDuration) -> FloatDuration;
 }
 
+/// Compute elapsed game time for a frame, keeping state between frames.
+pub trait StatefulStep {
+    /// Compute the time step for the next frame.
+    ///
+    /// `previous` is the `GameTime` of the previous frame, and `clock_multiplier` the
+    /// multiplier that the clock applies to the returned step.
+    fn next_step<Tz: TimeZone>(
+        &mut self,
+        wall_time: &FloatDuration,
+        previous: &GameTime<Tz>,
+        clock_multiplier: f64,
+    ) -> FloatDuration;
+}
+
+/// Combinators for composing time steps.
+///
+/// This is implemented for every `TimeStep`. Stateful steps have the same combinators in
+/// [`StatefulStepExt`](./trait.StatefulStepExt.html).
+pub trait TimeStepExt: TimeStep + Sized {
+    /// Limit the time step to the range `[min, max]`.
+    fn clamped(self, min: FloatDuration, max: FloatDuration) -> Clamped<Self> {
+        Clamped {
+            step: self,
+            min,
+            max,
+        }
+    }
+    /// Multiply the time step by `factor`.
+    fn scaled(self, factor: f64) -> Scaled<Self> {
+        Scaled { step: self, factor }
+    }
+    /// Transform the time step with `f`.
+    fn map<F>(self, f: F) -> Map<Self, F>
+    where
+        F: Fn(FloatDuration) -> FloatDuration,
+    {
+        Map { step: self, f }
+    }
+    /// Use the smaller of this time step and `other`.
+    fn min_of<U: TimeStep>(self, other: U) -> MinOf<Self, U> {
+        MinOf {
+            first: self,
+            second: other,
+        }
+    }
+    /// Use the larger of this time step and `other`.
+    fn max_of<U: TimeStep>(self, other: U) -> MaxOf<Self, U> {
+        MaxOf {
+            first: self,
+            second: other,
+        }
+    }
+    /// Round the time step to the nearest multiple of `quantum`.
+    ///
+    /// The rounding error is not carried into later frames. See
+    /// [`SmoothedStep`](./struct.SmoothedStep.html) for snapping that keeps game time in
+    /// step with wall time.
+    fn quantized(self, quantum: FloatDuration) -> Quantized<Self> {
+        Quantized {
+            step: self,
+            quantum,
+        }
+    }
+    /// Use `fallback` whenever this time step is zero, negative or not finite.
+    fn or_fallback<U: TimeStep>(self, fallback: U) -> Fallback<Self, U> {
+        Fallback {
+            first: self,
+            second: fallback,
+        }
+    }
+}
+
+/// Combinators for composing stateful time steps.
+///
+/// This is implemented for every `StatefulStep`, and mirrors
+/// [`TimeStepExt`](./trait.TimeStepExt.html). A plain `TimeStep` is combined with a
+/// stateful one by reference, as in `smoothed.max_of(&constant)`.
+pub trait StatefulStepExt: StatefulStep + Sized {
+    /// Limit the time step to the range `[min, max]`.
+    fn clamped(self, min: FloatDuration, max: FloatDuration) -> Clamped<Self> {
+        Clamped {
+            step: self,
+            min,
+            max,
+        }
+    }
+    /// Multiply the time step by `factor`.
+    fn scaled(self, factor: f64) -> Scaled<Self> {
+        Scaled { step: self, factor }
+    }
+    /// Transform the time step with `f`.
+    fn map<F>(self, f: F) -> Map<Self, F>
+    where
+        F: Fn(FloatDuration) -> FloatDuration,
+    {
+        Map { step: self, f }
+    }
+    /// Use the smaller of this time step and `other`.
+    ///
+    /// Both steps are advanced every frame.
+    fn min_of<U: StatefulStep>(self, other: U) -> MinOf<Self, U> {
+        MinOf {
+            first: self,
+            second: other,
+        }
+    }
+    /// Use the larger of this time step and `other`.
+    ///
+    /// Both steps are advanced every frame.
+    fn max_of<U: StatefulStep>(self, other: U) -> MaxOf<Self, U> {
+        MaxOf {
+            first: self,
+            second: other,
+        }
+    }
+    /// Round the time step to the nearest multiple of `quantum`.
+    fn quantized(self, quantum: FloatDuration) -> Quantized<Self> {
+        Quantized {
+            step: self,
+            quantum,
+        }
+    }
+    /// Use `fallback` whenever this time step is zero, negative or not finite.
+    ///
+    /// `fallback` is only advanced on the frames it is used.
+    fn or_fallback<U: StatefulStep>(self, fallback: U) -> Fallback<Self, U> {
+        Fallback {
+            first: self,
+            second: fallback,
+        }
+    }
+}
+
 /// A time step based on elapsed wall time.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct VariableStep {}
@@ -16,11 +201,86 @@ pub struct VariableStep {}
 pub struct FixedStep<'a, C: 'a + FrameCount + ?Sized> {
     counter: &'a C,
 }
+/// A fixed time step derived from a frame rate it owns.
+///
+/// Unlike `FixedStep`, this does not borrow a `FrameCount`, so it can be used while the
+/// counter is updated, such as with
+/// [`FrameRunner::do_frame`](../runner/struct.FrameRunner.html#method.do_frame).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedRateStep {
+    frame_rate: f64,
+}
 /// A specific, constant time step.
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct ConstantStep {
     step: FloatDuration,
 }
+/// A time step based on the average elapsed wall time of recent frames.
+///
+/// Averaging hides scheduling jitter in individual frame times. When a display refresh rate
+/// is set, each step is also snapped to a whole number of refresh intervals. The difference
+/// between wall time and game time is carried into later frames, so total game time keeps
+/// tracking total wall time.
+#[derive(Debug, Clone)]
+pub struct SmoothedStep {
+    max_samples: u32,
+    refresh_rate: Option<f64>,
+    past_data: VecDeque<FloatDuration>,
+    carried_error: FloatDuration,
+}
+/// A time step computed by a closure from the elapsed wall time.
+#[derive(Debug, Clone, Copy)]
+pub struct FnStep<F> {
+    f: F,
+}
+
+/// A time step limited to a range, see
+/// [`TimeStepExt::clamped`](./trait.TimeStepExt.html#method.clamped).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clamped<S> {
+    step: S,
+    min: FloatDuration,
+    max: FloatDuration,
+}
+/// A multiplied time step, see [`TimeStepExt::scaled`](./trait.TimeStepExt.html#method.scaled).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scaled<S> {
+    step: S,
+    factor: f64,
+}
+/// A transformed time step, see [`TimeStepExt::map`](./trait.TimeStepExt.html#method.map).
+#[derive(Debug, Clone, Copy)]
+pub struct Map<S, F> {
+    step: S,
+    f: F,
+}
+/// The smaller of two time steps, see
+/// [`TimeStepExt::min_of`](./trait.TimeStepExt.html#method.min_of).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinOf<A, B> {
+    first: A,
+    second: B,
+}
+/// The larger of two time steps, see
+/// [`TimeStepExt::max_of`](./trait.TimeStepExt.html#method.max_of).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaxOf<A, B> {
+    first: A,
+    second: B,
+}
+/// A rounded time step, see [`TimeStepExt::quantized`](./trait.TimeStepExt.html#method.quantized).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantized<S> {
+    step: S,
+    quantum: FloatDuration,
+}
+/// A time step with a fallback, see
+/// [`TimeStepExt::or_fallback`](./trait.TimeStepExt.html#method.or_fallback).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fallback<A, B> {
+    first: A,
+    second: B,
+}
 
 impl VariableStep {
     /// Construct a new `VariableStep` object.
@@ -37,6 +297,35 @@ where
         FixedStep { counter: counter }
     }
 }
+impl FixedRateStep {
+    /// Construct a new `FixedRateStep` object with a frame rate of `frame_rate`.
+    pub fn new(frame_rate: f64) -> FixedRateStep {
+        FixedRateStep { frame_rate }
+    }
+    /// Construct a new `FixedRateStep` object with the set frame rate in `counter`.
+    pub fn from_counter<C: FrameCount + ?Sized>(counter: &C) -> FixedRateStep {
+        FixedRateStep::new(counter.target_frame_rate())
+    }
+    /// Construct a new `FixedRateStep` object with the set frame rate of the counter in
+    /// `runner`.
+    pub fn from_runner<C, Tz>(runner: &FrameRunner<C, Tz>) -> FixedRateStep
+    where
+        C: FrameCount,
+        Tz: TimeZone,
+    {
+        FixedRateStep::from_counter(runner.counter())
+    }
+
+    /// Return the frame rate of the time step.
+    pub fn frame_rate(&self) -> f64 {
+        self.frame_rate
+    }
+    /// Set the frame rate of the time step.
+    pub fn set_frame_rate(&mut self, frame_rate: f64) -> &mut FixedRateStep {
+        self.frame_rate = frame_rate;
+        self
+    }
+}
 impl ConstantStep {
     /// Construct a new `ConstantStep` object with a set time step `step`.
     pub fn new(step: FloatDuration) -> ConstantStep {
@@ -48,6 +337,71 @@ impl ConstantStep {
     }
 }
 
+impl SmoothedStep {
+    /// Construct a new `SmoothedStep` averaging a default number of frames.
+    pub fn new() -> SmoothedStep {
+        SmoothedStep::with_max_samples(DEFAULT_SMOOTHING_SAMPLES)
+    }
+    /// Construct a new `SmoothedStep` averaging up to `max_samples` frames.
+    pub fn with_max_samples(max_samples: u32) -> SmoothedStep {
+        SmoothedStep {
+            max_samples: max_samples.max(1),
+            refresh_rate: None,
+            past_data: VecDeque::new(),
+            carried_error: FloatDuration::zero(),
+        }
+    }
+
+    /// Return the number of frames to average over.
+    pub fn max_samples(&self) -> u32 {
+        self.max_samples
+    }
+    /// Return the display refresh rate that steps are snapped to, if any.
+    pub fn refresh_rate(&self) -> Option<f64> {
+        self.refresh_rate
+    }
+    /// Set the display refresh rate, in Hz, that steps are snapped to.
+    ///
+    /// With a refresh rate set, every step is a whole number of refresh intervals, and at
+    /// least one. Panics if the refresh rate is not positive and finite.
+    pub fn set_refresh_rate(&mut self, refresh_rate: Option<f64>) -> &mut SmoothedStep {
+        if let Some(rate) = refresh_rate {
+            assert!(
+                rate > 0.0 && rate.is_finite(),
+                "refresh rate must be positive and finite, got {}",
+                rate
+            );
+        }
+        self.refresh_rate = refresh_rate;
+        self
+    }
+    /// Return the wall time not yet passed on as game time.
+    ///
+    /// This is negative when game time is ahead of wall time.
+    pub fn carried_error(&self) -> FloatDuration {
+        self.carried_error
+    }
+    /// Discard all past frames and the carried error.
+    pub fn reset(&mut self) {
+        self.past_data.clear();
+        self.carried_error = FloatDuration::zero();
+    }
+}
+impl Default for SmoothedStep {
+    fn default() -> SmoothedStep {
+        SmoothedStep::new()
+    }
+}
+impl<F> FnStep<F>
+where
+    F: Fn(&FloatDuration) -> FloatDuration,
+{
+    /// Construct a new `FnStep` computing the time step with `f`.
+    pub fn new(f: F) -> FnStep<F> {
+        FnStep { f }
+    }
+}
+
 impl TimeStep for VariableStep {
     fn time_step(&self, wall_time: &FloatDuration) -> FloatDuration {
         *wall_time
@@ -61,17 +415,224 @@ where
         self.counter.target_time_per_frame()
     }
 }
+impl TimeStep for FixedRateStep {
+    fn time_step(&self, _: &FloatDuration) -> FloatDuration {
+        FloatDuration::seconds(1.0) / self.frame_rate
+    }
+}
 impl TimeStep for ConstantStep {
     fn time_step(&self, _: &FloatDuration) -> FloatDuration {
         self.step
     }
 }
+impl<F> TimeStep for FnStep<F>
+where
+    F: Fn(&FloatDuration) -> FloatDuration,
+{
+    fn time_step(&self, wall_time: &FloatDuration) -> FloatDuration {
+        (self.f)(wall_time)
+    }
+}
+
+impl<T: TimeStep> TimeStepExt for T {}
+
+impl<S: TimeStep> TimeStep for Clamped<S> {
+    fn time_step(&self, wall_time: &FloatDuration) -> FloatDuration {
+        self.step.time_step(wall_time).max(self.min).min(self.max)
+    }
+}
+impl<S: TimeStep> TimeStep for Scaled<S> {
+    fn time_step(&self, wall_time: &FloatDuration) -> FloatDuration {
+        self.step.time_step(wall_time) * self.factor
+    }
+}
+impl<S, F> TimeStep for Map<S, F>
+where
+    S: TimeStep,
+    F: Fn(FloatDuration) -> FloatDuration,
+{
+    fn time_step(&self, wall_time: &FloatDuration) -> FloatDuration {
+        (self.f)(self.step.time_step(wall_time))
+    }
+}
+impl<A: TimeStep, B: TimeStep> TimeStep for MinOf<A, B> {
+    fn time_step(&self, wall_time: &FloatDuration) -> FloatDuration {
+        let first = self.first.time_step(wall_time);
+        first.min(self.second.time_step(wall_time))
+    }
+}
+impl<A: TimeStep, B: TimeStep> TimeStep for MaxOf<A, B> {
+    fn time_step(&self, wall_time: &FloatDuration) -> FloatDuration {
+        let first = self.first.time_step(wall_time);
+        first.max(self.second.time_step(wall_time))
+    }
+}
+impl<S: TimeStep> TimeStep for Quantized<S> {
+    fn time_step(&self, wall_time: &FloatDuration) -> FloatDuration {
+        let step = self.step.time_step(wall_time);
+        if self.quantum.is_zero() {
+            step
+        } else {
+            self.quantum * (step / self.quantum).round()
+        }
+    }
+}
+impl<A: TimeStep, B: TimeStep> TimeStep for Fallback<A, B> {
+    fn time_step(&self, wall_time: &FloatDuration) -> FloatDuration {
+        let step = self.first.time_step(wall_time);
+        if step.as_seconds() > 0.0 && step.as_seconds().is_finite() {
+            step
+        } else {
+            self.second.time_step(wall_time)
+        }
+    }
+}
+
+impl<S: StatefulStep> StatefulStepExt for S {}
+
+impl<S: StatefulStep> StatefulStep for Clamped<S> {
+    fn next_step<Tz: TimeZone>(
+        &mut self,
+        wall_time: &FloatDuration,
+        previous: &GameTime<Tz>,
+        clock_multiplier: f64,
+    ) -> FloatDuration {
+        let step = self.step.next_step(wall_time, previous, clock_multiplier);
+        step.max(self.min).min(self.max)
+    }
+}
+impl<S: StatefulStep> StatefulStep for Scaled<S> {
+    fn next_step<Tz: TimeZone>(
+        &mut self,
+        wall_time: &FloatDuration,
+        previous: &GameTime<Tz>,
+        clock_multiplier: f64,
+    ) -> FloatDuration {
+        self.step.next_step(wall_time, previous, clock_multiplier) * self.factor
+    }
+}
+impl<S, F> StatefulStep for Map<S, F>
+where
+    S: StatefulStep,
+    F: Fn(FloatDuration) -> FloatDuration,
+{
+    fn next_step<Tz: TimeZone>(
+        &mut self,
+        wall_time: &FloatDuration,
+        previous: &GameTime<Tz>,
+        clock_multiplier: f64,
+    ) -> FloatDuration {
+        (self.f)(self.step.next_step(wall_time, previous, clock_multiplier))
+    }
+}
+impl<A: StatefulStep, B: StatefulStep> StatefulStep for MinOf<A, B> {
+    fn next_step<Tz: TimeZone>(
+        &mut self,
+        wall_time: &FloatDuration,
+        previous: &GameTime<Tz>,
+        clock_multiplier: f64,
+    ) -> FloatDuration {
+        let first = self.first.next_step(wall_time, previous, clock_multiplier);
+        first.min(self.second.next_step(wall_time, previous, clock_multiplier))
+    }
+}
+impl<A: StatefulStep, B: StatefulStep> StatefulStep for MaxOf<A, B> {
+    fn next_step<Tz: TimeZone>(
+        &mut self,
+        wall_time: &FloatDuration,
+        previous: &GameTime<Tz>,
+        clock_multiplier: f64,
+    ) -> FloatDuration {
+        let first = self.first.next_step(wall_time, previous, clock_multiplier);
+        first.max(self.second.next_step(wall_time, previous, clock_multiplier))
+    }
+}
+impl<S: StatefulStep> StatefulStep for Quantized<S> {
+    fn next_step<Tz: TimeZone>(
+        &mut self,
+        wall_time: &FloatDuration,
+        previous: &GameTime<Tz>,
+        clock_multiplier: f64,
+    ) -> FloatDuration {
+        let step = self.step.next_step(wall_time, previous, clock_multiplier);
+        if self.quantum.is_zero() {
+            step
+        } else {
+            self.quantum * (step / self.quantum).round()
+        }
+    }
+}
+impl<A: StatefulStep, B: StatefulStep> StatefulStep for Fallback<A, B> {
+    fn next_step<Tz: TimeZone>(
+        &mut self,
+        wall_time: &FloatDuration,
+        previous: &GameTime<Tz>,
+        clock_multiplier: f64,
+    ) -> FloatDuration {
+        let step = self.first.next_step(wall_time, previous, clock_multiplier);
+        if step.as_seconds() > 0.0 && step.as_seconds().is_finite() {
+            step
+        } else {
+            self.second.next_step(wall_time, previous, clock_multiplier)
+        }
+    }
+}
+
+impl StatefulStep for SmoothedStep {
+    fn next_step<Tz: TimeZone>(
+        &mut self,
+        wall_time: &FloatDuration,
+        _: &GameTime<Tz>,
+        _: f64,
+    ) -> FloatDuration {
+        self.past_data.push_back(*wall_time);
+        while self.past_data.len() > self.max_samples as usize {
+            self.past_data.pop_front();
+        }
+        let samples = self.past_data.len() as f64;
+        let average = self.past_data.iter().sum::<FloatDuration>() / samples;
+
+        let step = match self.refresh_rate {
+            Some(refresh_rate) => {
+                let interval = FloatDuration::seconds(1.0 / refresh_rate);
+                let intervals = ((average + self.carried_error) / interval).round();
+                interval * intervals.max(1.0)
+            }
+            // Pay the error back gradually, so that it does not reintroduce the jitter.
+            None => average + self.carried_error / samples,
+        };
+        self.carried_error += *wall_time - step;
+        step
+    }
+}
+
+impl<T: TimeStep + ?Sized> StatefulStep for &T {
+    fn next_step<Tz: TimeZone>(
+        &mut self,
+        wall_time: &FloatDuration,
+        _: &GameTime<Tz>,
+        _: f64,
+    ) -> FloatDuration {
+        self.time_step(wall_time)
+    }
+}
+impl<S: StatefulStep + ?Sized> StatefulStep for &mut S {
+    fn next_step<Tz: TimeZone>(
+        &mut self,
+        wall_time: &FloatDuration,
+        previous: &GameTime<Tz>,
+        clock_multiplier: f64,
+    ) -> FloatDuration {
+        (**self).next_step(wall_time, previous, clock_multiplier)
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use clock::GameClock;
     use framerate::{counter, sample};
+    use runner::FrameRunner;
 
     #[test]
     fn test_fixed_step() {
@@ -91,6 +652,27 @@ mod tests {
         assert_eq!(time.total_game_time(), FloatDuration::seconds(1.0));
     }
 
+    #[test]
+    fn test_fixed_rate_step() {
+        let counter =
+            counter::FrameCounter::new(20.0, sample::RunningAverageSampler::with_max_samples(20));
+        let mut runner = FrameRunner::new(GameClock::new(), counter);
+
+        for _ in 0..20 {
+            runner.do_frame(&FixedRateStep::from_runner(&runner), |time| {
+                assert_eq!(time.elapsed_game_time(), FloatDuration::seconds(1.0 / 20.0));
+            });
+        }
+        let time = runner.clock().last_frame_time();
+        assert_eq!(time.total_game_time(), FloatDuration::seconds(1.0));
+
+        runner.counter_mut().set_target_frame_rate(1000.0);
+        let step = FixedRateStep::from_counter(runner.counter());
+        assert_eq!(step, FixedRateStep::new(1000.0));
+        let time = runner.tick(&step);
+        assert_eq!(time.elapsed_game_time(), FloatDuration::milliseconds(1.0));
+    }
+
     #[test]
     fn test_null_step() {
         let mut clock = GameClock::new();
@@ -107,4 +689,186 @@ mod tests {
         );
 
     }
+
+    #[test]
+    fn test_smoothed_step() {
+        let clock = GameClock::new();
+        let mut step = SmoothedStep::with_max_samples(4);
+        let jittery = [14.0, 19.0, 15.0, 18.0];
+
+        let mut total_wall = FloatDuration::zero();
+        let mut total_game = FloatDuration::zero();
+        for i in 0..100 {
+            let wall = FloatDuration::milliseconds(jittery[i % 4]);
+            let game = step.next_step(&wall, clock.last_frame_time(), 1.0);
+            if i >= 4 {
+                assert!(relative_eq!(game.as_milliseconds(), 16.5, epsilon = 0.5));
+            }
+            total_wall += wall;
+            total_game += game;
+        }
+        assert!(relative_eq!(
+            (total_wall - total_game).as_seconds(),
+            step.carried_error().as_seconds(),
+            epsilon = 1e-9
+        ));
+        assert!(step.carried_error().abs() < FloatDuration::milliseconds(5.0));
+    }
+
+    #[test]
+    fn test_smoothed_step_refresh_rate() {
+        let mut clock = GameClock::new();
+        let start = clock.start_wall_time();
+        let mut step = SmoothedStep::new();
+        step.set_refresh_rate(Some(60.0));
+        let interval = FloatDuration::seconds(1.0 / 60.0);
+
+        let mut wall_ms = 0;
+        let mut long_frames = 0;
+        for i in 0..120 {
+            wall_ms += if i % 2 == 0 { 15 } else { 19 };
+            let frame_start = start + ::chrono::Duration::milliseconds(wall_ms);
+            let time = clock.tick_with_wall_time(&mut step, frame_start);
+            let intervals = time.elapsed_game_time() / interval;
+            assert!(relative_eq!(intervals, intervals.round(), epsilon = 1e-9));
+            if intervals > 1.5 {
+                long_frames += 1;
+            }
+        }
+        // Frames average 17ms, so game time skips an extra refresh every 50 frames or so.
+        assert_eq!(long_frames, 2);
+        let time = clock.last_frame_time();
+        let drift = time.total_wall_time() - time.total_game_time();
+        assert!(drift.abs() <= interval);
+
+        step.reset();
+        assert_eq!(step.carried_error(), FloatDuration::zero());
+    }
+
+    #[test]
+    #[should_panic(expected = "refresh rate must be positive and finite")]
+    fn test_smoothed_step_zero_refresh_rate() {
+        SmoothedStep::new().set_refresh_rate(Some(0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "refresh rate must be positive and finite")]
+    fn test_smoothed_step_nan_refresh_rate() {
+        SmoothedStep::new().set_refresh_rate(Some(f64::NAN));
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingStep {
+        seen: Vec<(u64, f64)>,
+    }
+
+    impl StatefulStep for RecordingStep {
+        fn next_step<Tz: TimeZone>(
+            &mut self,
+            _: &FloatDuration,
+            previous: &GameTime<Tz>,
+            clock_multiplier: f64,
+        ) -> FloatDuration {
+            self.seen.push((previous.frame_number(), clock_multiplier));
+            FloatDuration::milliseconds(10.0 * self.seen.len() as f64)
+        }
+    }
+
+    #[test]
+    fn test_stateful_step() {
+        let mut clock = GameClock::new();
+        let mut step = RecordingStep::default();
+
+        clock.tick(&mut step);
+        clock.set_clock_multiplier(2.0);
+        let time = clock.tick(&mut step);
+        assert_eq!(time.elapsed_game_time(), FloatDuration::milliseconds(40.0));
+
+        let counter =
+            counter::FrameCounter::new(20.0, sample::RunningAverageSampler::with_max_samples(20));
+        let mut runner = FrameRunner::new(clock, counter);
+        runner.do_frame(&mut step, |time| {
+            assert_eq!(time.elapsed_game_time(), FloatDuration::milliseconds(60.0));
+        });
+        assert_eq!(step.seen, vec![(0, 1.0), (1, 2.0), (2, 2.0)]);
+
+        // Plain time steps are passed by reference as before.
+        let time = runner.tick(&ConstantStep::new(FloatDuration::milliseconds(5.0)));
+        assert_eq!(time.elapsed_game_time(), FloatDuration::milliseconds(10.0));
+    }
+
+    #[test]
+    fn test_combinators() {
+        let ms = FloatDuration::milliseconds;
+        let variable = VariableStep::new();
+
+        let clamped = variable.clamped(ms(10.0), ms(20.0));
+        assert_eq!(clamped.time_step(&ms(5.0)), ms(10.0));
+        assert_eq!(clamped.time_step(&ms(15.0)), ms(15.0));
+        assert_eq!(clamped.time_step(&ms(25.0)), ms(20.0));
+
+        assert_eq!(variable.scaled(2.0).time_step(&ms(5.0)), ms(10.0));
+        assert_eq!(variable.map(|dt| dt + ms(1.0)).time_step(&ms(5.0)), ms(6.0));
+
+        let constant = ConstantStep::new(ms(10.0));
+        assert_eq!(variable.min_of(constant).time_step(&ms(30.0)), ms(10.0));
+        assert_eq!(variable.max_of(constant).time_step(&ms(30.0)), ms(30.0));
+
+        let quantized = variable.quantized(ms(10.0));
+        assert!(relative_eq!(quantized.time_step(&ms(14.0)).as_milliseconds(), 10.0));
+        assert!(relative_eq!(quantized.time_step(&ms(16.0)).as_milliseconds(), 20.0));
+
+        let fallback = ConstantStep::null_step().or_fallback(constant);
+        assert_eq!(fallback.time_step(&ms(30.0)), ms(10.0));
+        assert_eq!(constant.or_fallback(variable).time_step(&ms(30.0)), ms(10.0));
+    }
+
+    #[test]
+    fn test_stateful_combinators() {
+        let ms = FloatDuration::milliseconds;
+        let mut clock = GameClock::new();
+        let start = clock.start_wall_time();
+        let min_step = ConstantStep::new(ms(10.0));
+        let mut step = SmoothedStep::with_max_samples(2)
+            .clamped(ms(0.0), ms(30.0))
+            .max_of(&min_step);
+
+        let mut game_ms = Vec::new();
+        for &wall_ms in &[4, 8, 100, 100] {
+            let frame_start = start + ::chrono::Duration::milliseconds(wall_ms);
+            let time = clock.tick_with_wall_time(&mut step, frame_start);
+            game_ms.push(time.elapsed_game_time().as_milliseconds());
+        }
+        // Frames of 4, 4, 92 and 0ms of wall time.
+        assert!(relative_eq!(game_ms[0], 10.0));
+        assert!(relative_eq!(game_ms[1], 10.0));
+        assert!(relative_eq!(game_ms[2], 30.0));
+        assert!(relative_eq!(game_ms[3], 30.0));
+
+        let mut smoothed = SmoothedStep::new();
+        {
+            let mut halved = (&mut smoothed).scaled(0.5).map(|dt| dt + ms(1.0));
+            let frame_start = start + ::chrono::Duration::milliseconds(120);
+            let time = clock.tick_with_wall_time(&mut halved, frame_start);
+            assert!(relative_eq!(time.elapsed_game_time().as_milliseconds(), 11.0));
+        }
+        assert_eq!(smoothed.carried_error(), FloatDuration::zero());
+    }
+
+    #[test]
+    fn test_fn_step() {
+        let mut clock = GameClock::new();
+        let count = counter::FrameCounter::new(20.0, sample::LinearAverageSampler::new());
+        let half_rate = FnStep::new(|_: &FloatDuration| count.target_time_per_frame() / 2.0);
+
+        let time = clock.tick(&half_rate);
+        assert_eq!(time.elapsed_game_time(), FloatDuration::milliseconds(25.0));
+
+        let time = clock.tick(&FixedStep::new(&count).scaled(0.5).max_of(half_rate));
+        assert_eq!(time.elapsed_game_time(), FloatDuration::milliseconds(25.0));
+
+        let ms = FloatDuration::milliseconds;
+        let doubled = FnStep::new(|wall_time: &FloatDuration| *wall_time * 2.0);
+        assert_eq!(doubled.time_step(&ms(5.0)), ms(10.0));
+    }
 }
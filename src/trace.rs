@@ -0,0 +1,262 @@
+//! Export of frame timings in the Chrome trace event format.
+//!
+//! [`ChromeTraceWriter`](./struct.ChromeTraceWriter.html) streams frames and profiler scopes
+//! to any `io::Write` as they are recorded, using the JSON array form of the trace event
+//! format. The output can be opened in `chrome://tracing` or [Perfetto](https://ui.perfetto.dev).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use game_time::{GameClock, FloatDuration};
+//! use game_time::trace::ChromeTraceWriter;
+//! use game_time::step;
+//!
+//! let mut clock = GameClock::new();
+//! let step = step::ConstantStep::new(FloatDuration::milliseconds(16.0));
+//! let mut trace = ChromeTraceWriter::new(Vec::new()).unwrap();
+//!
+//! for _ in 0..10 {
+//!     let time = clock.tick(&step);
+//!     trace.write_frame(&time).unwrap();
+//! }
+//!
+//! let json = String::from_utf8(trace.finish().unwrap()).unwrap();
+//! assert!(json.starts_with('['));
+//! ```
+use std::io::{self, Write};
+
+use chrono::{self, TimeZone};
+use float_duration::{FloatDuration, TimePoint};
+
+use clock::GameTime;
+use profile::FrameProfile;
+
+const PROCESS_ID: u32 = 1;
+const THREAD_ID: u32 = 1;
+
+/// A streaming writer of Chrome trace events.
+///
+/// Each frame is written as a complete event spanning from its start to the start of the
+/// next frame, so a frame is held back until the following one is written. Only that one
+/// frame is buffered, keeping memory use bounded regardless of the length of the trace.
+///
+/// Timestamps are relative to the start of the first frame or scope written.
+///
+/// The trace is completed by [`finish`](#method.finish). If the writer is dropped instead,
+/// the last frame is lost, but the output written so far can still be loaded.
+#[derive(Debug)]
+pub struct ChromeTraceWriter<W: Write> {
+    writer: W,
+    origin: Option<chrono::DateTime<chrono::Utc>>,
+    pending_frame: Option<GameTime<chrono::Utc>>,
+    events_written: u64,
+}
+
+impl<W: Write> ChromeTraceWriter<W> {
+    /// Construct a new `ChromeTraceWriter`, writing the start of the trace to `writer`.
+    pub fn new(mut writer: W) -> io::Result<ChromeTraceWriter<W>> {
+        writer.write_all(b"[\n")?;
+        Ok(ChromeTraceWriter {
+            writer,
+            origin: None,
+            pending_frame: None,
+            events_written: 0,
+        })
+    }
+
+    /// Return the number of events written so far.
+    pub fn events_written(&self) -> u64 {
+        self.events_written
+    }
+    /// Return a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Record a frame.
+    ///
+    /// The frame is written once the next frame is recorded or the trace is finished.
+    pub fn write_frame<Tz: TimeZone>(&mut self, time: &GameTime<Tz>) -> io::Result<()> {
+        let time = time.with_timezone(&chrono::Utc);
+        if let Some(prev) = self.pending_frame.take() {
+            let duration = time.frame_start_time()
+                .float_duration_since(prev.frame_start_time())
+                .unwrap();
+            self.write_frame_event(&prev, Some(duration))?;
+        }
+        self.pending_frame = Some(time);
+        Ok(())
+    }
+    /// Write the scopes of a profiled frame.
+    pub fn write_profile<Tz: TimeZone>(&mut self, profile: &FrameProfile<Tz>) -> io::Result<()> {
+        let frame_start_time = profile.frame_start_time().with_timezone(&chrono::Utc);
+        for scope in profile.scopes() {
+            let ts = self.timestamp(frame_start_time) + micros(scope.start());
+            let event = format!(
+                "{{\"name\":\"{}\",\"cat\":\"scope\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\
+                 \"pid\":{},\"tid\":{},\"args\":{{\"frame_number\":{},\"depth\":{}}}}}",
+                escape(scope.name()),
+                ts,
+                micros(scope.duration()),
+                PROCESS_ID,
+                THREAD_ID,
+                profile.frame_number(),
+                scope.depth()
+            );
+            self.write_event(&event)?;
+        }
+        Ok(())
+    }
+    /// Flush the underlying writer.
+    ///
+    /// The most recent frame is not written until the next frame is recorded.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+    /// Write any buffered frame and the end of the trace, returning the underlying writer.
+    ///
+    /// As its end is not known, the last frame is written as an instant event.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(last) = self.pending_frame.take() {
+            self.write_frame_event(&last, None)?;
+        }
+        self.writer.write_all(b"\n]\n")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_frame_event(
+        &mut self,
+        time: &GameTime<chrono::Utc>,
+        duration: Option<FloatDuration>,
+    ) -> io::Result<()> {
+        let ts = self.timestamp(time.frame_start_time());
+        let phase = match duration {
+            Some(duration) => format!("\"ph\":\"X\",\"dur\":{:.3}", micros(duration)),
+            None => "\"ph\":\"i\",\"s\":\"t\"".to_string(),
+        };
+        let event = format!(
+            "{{\"name\":\"Frame {}\",\"cat\":\"frame\",{},\"ts\":{:.3},\"pid\":{},\"tid\":{},\
+             \"args\":{{\"frame_number\":{},\"elapsed_wall_ms\":{},\"elapsed_game_ms\":{},\
+             \"total_game_ms\":{}}}}}",
+            time.frame_number(),
+            phase,
+            ts,
+            PROCESS_ID,
+            THREAD_ID,
+            time.frame_number(),
+            time.elapsed_wall_time().as_milliseconds(),
+            time.elapsed_game_time().as_milliseconds(),
+            time.total_game_time().as_milliseconds()
+        );
+        self.write_event(&event)?;
+
+        let counter = format!(
+            "{{\"name\":\"Game delta (ms)\",\"ph\":\"C\",\"ts\":{:.3},\"pid\":{},\
+             \"args\":{{\"elapsed_game_ms\":{}}}}}",
+            ts,
+            PROCESS_ID,
+            time.elapsed_game_time().as_milliseconds()
+        );
+        self.write_event(&counter)
+    }
+
+    fn write_event(&mut self, event: &str) -> io::Result<()> {
+        if self.events_written > 0 {
+            self.writer.write_all(b",\n")?;
+        }
+        self.writer.write_all(event.as_bytes())?;
+        self.events_written += 1;
+        Ok(())
+    }
+
+    /// Return the time since the origin in microseconds, setting the origin if it is unset.
+    fn timestamp(&mut self, time: chrono::DateTime<chrono::Utc>) -> f64 {
+        let origin = *self.origin.get_or_insert(time);
+        micros(time.float_duration_since(origin).unwrap())
+    }
+}
+
+fn micros(duration: FloatDuration) -> f64 {
+    duration.as_microseconds()
+}
+
+/// Escape a string for use within a JSON string literal.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::GameClock;
+    use profile::Profiler;
+    use step;
+
+    #[test]
+    fn test_frame_events() {
+        let mut clock = GameClock::new();
+        let step = step::ConstantStep::new(FloatDuration::milliseconds(20.0));
+        let start_time = clock.start_wall_time();
+        let mut trace = ChromeTraceWriter::new(Vec::new()).unwrap();
+
+        for i in 0..3 {
+            let frame_time = start_time + chrono::Duration::milliseconds(10 * (i + 1));
+            let time = clock.tick_with_wall_time(&step, frame_time);
+            trace.write_frame(&time).unwrap();
+        }
+        // The last frame is held back until the trace is finished.
+        assert_eq!(trace.events_written(), 4);
+
+        let json = String::from_utf8(trace.finish().unwrap()).unwrap();
+        let lines: Vec<_> = json.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[0], "[");
+        assert_eq!(lines[7], "]");
+        assert!(lines[1].starts_with("{\"name\":\"Frame 1\",\"cat\":\"frame\",\"ph\":\"X\""));
+        assert!(lines[1].contains("\"dur\":10000.000,\"ts\":0.000"));
+        assert!(lines[1].contains("\"elapsed_game_ms\":20"));
+        assert!(lines[1].ends_with("},"));
+        assert!(lines[2].contains("\"ph\":\"C\""));
+        assert!(lines[3].contains("\"ts\":10000.000"));
+        assert!(lines[5].contains("\"name\":\"Frame 3\",\"cat\":\"frame\",\"ph\":\"i\""));
+        assert!(lines[6].ends_with('}'));
+    }
+
+    #[test]
+    fn test_profile_events() {
+        let mut clock = GameClock::new();
+        let profiler = Profiler::new();
+        let time = clock.tick(&step::VariableStep::new());
+
+        profiler.begin_frame(&time);
+        {
+            let _scope = profiler.scope("update \"main\"");
+        }
+        profiler.end_frame();
+
+        let mut trace = ChromeTraceWriter::new(Vec::new()).unwrap();
+        trace.write_profile(&profiler.last_frame().unwrap()).unwrap();
+        let json = String::from_utf8(trace.finish().unwrap()).unwrap();
+
+        assert!(json.contains("\"name\":\"update \\\"main\\\"\",\"cat\":\"scope\",\"ph\":\"X\""));
+        assert!(json.contains("\"args\":{\"frame_number\":1,\"depth\":0}"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a\"b\\c\n\u{1}"), "a\\\"b\\\\c\\n\\u0001");
+    }
+}